@@ -1,18 +1,275 @@
-use structopt::StructOpt;
+use clap::{ArgAction, Args, Parser, Subcommand};
+use log::LevelFilter;
+use crate::domain::settings::Settings;
+use crate::fuzzer::output::OutputFormat;
 
-#[derive(Debug, StructOpt)]
-pub struct CliFuzzEndpoint {
-  #[structopt(short)]
-  pub(crate) input_file: String,
+#[derive(Debug, Parser)]
+#[command(name = "fuzzy", version, about = "Fuzzer for http apis")]
+pub struct Cli {
+  /// More logging, -v for debug and -vv for trace
+  #[arg(short, long, action = ArgAction::Count, global = true)]
+  pub verbose: u8,
+
+  /// Less logging, -q for warnings, -qq for errors and -qqq for none
+  #[arg(short, long, action = ArgAction::Count, global = true, conflicts_with = "verbose")]
+  pub quiet: u8,
+
+  #[command(subcommand)]
+  pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+  /// Fuzz the paths of an input file
+  Run(RunArgs),
+  /// Check an input file and dictionaries without sending any requests
+  Validate(ValidateArgs),
+  /// Inspect dictionaries
+  Dict {
+    #[command(subcommand)]
+    command: DictCommand,
+  },
+  /// Send the requests of a json lines output file again
+  Replay(ReplayArgs),
+  /// Generate an input file from another description of the api
+  Import {
+    #[command(subcommand)]
+    command: ImportCommand,
+  },
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+  /// Input file describing the paths to fuzz
+  #[arg(short, long)]
+  pub input_file: String,
+
+  #[command(flatten)]
+  pub settings: SettingsArgs,
+
+  /// Header to send the request id in, e.g. X-Fuzzy-Request-Id
+  #[arg(long)]
+  pub request_id_header: Option<String>,
+}
+
+/// Flags overriding the `settings` section of the input file.
+#[derive(Debug, Default, Args)]
+pub struct SettingsArgs {
+  /// Directory to load dictionaries from [default: ./dictionary]
+  #[arg(short, long)]
+  pub dictionary_dir: Option<String>,
+
+  /// File results are written to [default: output.txt]
+  #[arg(short, long)]
+  pub output: Option<String>,
+
+  /// Format of the output file [default: jsonl for .jsonl files, text otherwise]
+  #[arg(short, long, value_enum)]
+  pub format: Option<OutputFormat>,
+
+  /// Maximum number of requests in flight [default: 10]
+  #[arg(short, long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+  pub concurrency: Option<usize>,
+
+  /// Capacity of the channel results are passed through [default: 32]
+  #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+  pub channel_size: Option<usize>,
+
+  /// Seconds between metrics reports [default: 5]
+  #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<u64>::new().range(1..))]
+  pub metrics_interval: Option<u64>,
+
+  /// Benign requests to send per path before fuzzing it, responses are then reported when they deviate from these
+  /// instead of by expected_status, which 0 checks instead [default: 3]
+  #[arg(long)]
+  pub baseline_requests: Option<usize>,
+
+  /// Score from which a response deviating from the baseline is reported [default: 1.0]
+  #[arg(long)]
+  pub anomaly_threshold: Option<f64>,
+
+  /// Flag responses taking this many times the baseline median latency, or the latency of a request with the
+  /// defaults without a baseline, 0 turns this off [default: 5.0]
+  #[arg(long)]
+  pub delay_factor: Option<f64>,
+
+  /// Requests per second over every host, 0 is unlimited [default: 0]
+  #[arg(long)]
+  pub rate_limit: Option<f64>,
+
+  /// Requests per second to each host, 0 is unlimited [default: 0]
+  #[arg(long)]
+  pub host_rate_limit: Option<f64>,
+
+  /// Requests sent at once after a pause before the rate limits apply [default: 1]
+  #[arg(long)]
+  pub burst: Option<usize>,
+
+  /// Longest random pause in milliseconds before each request [default: 0]
+  #[arg(long)]
+  pub jitter_ms: Option<u64>,
+
+  /// Only fuzz with these dictionaries, by id or by tag:, cve: or file: selector, e.g. --include tag:sqli, can be
+  /// repeated
+  #[arg(long)]
+  pub include: Vec<String>,
+
+  /// Do not fuzz with these dictionaries, same selectors as --include, can be repeated
+  #[arg(long)]
+  pub exclude: Vec<String>,
+}
+
+impl SettingsArgs {
+  pub fn to_settings(&self) -> Settings {
+    Settings {
+      dictionary_dir: self.dictionary_dir.clone(),
+      output: self.output.clone(),
+      format: self.format,
+      concurrency: self.concurrency,
+      channel_size: self.channel_size,
+      metrics_interval: self.metrics_interval,
+      baseline_requests: self.baseline_requests,
+      anomaly_threshold: self.anomaly_threshold,
+      delay_factor: self.delay_factor,
+      rate_limit: self.rate_limit,
+      host_rate_limit: self.host_rate_limit,
+      burst: self.burst,
+      jitter_ms: self.jitter_ms,
+      include: (!self.include.is_empty()).then(|| self.include.clone()),
+      exclude: (!self.exclude.is_empty()).then(|| self.exclude.clone()),
+    }
+  }
+}
+
+#[derive(Debug, Args)]
+pub struct ValidateArgs {
+  /// Input file to check
+  #[arg(short, long)]
+  pub input_file: String,
+
+  /// Directory of dictionaries to check [default: ./dictionary]
+  #[arg(short, long)]
+  pub dictionary_dir: Option<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum DictCommand {
+  /// List the dictionaries with their number of values
+  List {
+    /// Directory to load dictionaries from
+    #[arg(short, long, default_value = "./dictionary")]
+    dictionary_dir: String,
+
+    /// Only list these dictionaries, same selectors as run --include
+    #[arg(long)]
+    include: Vec<String>,
+
+    /// Do not list these dictionaries, same selectors as run --exclude
+    #[arg(long)]
+    exclude: Vec<String>,
+  },
+}
+
+#[derive(Debug, Args)]
+pub struct ReplayArgs {
+  /// Json lines output file of a previous run
+  #[arg(short, long)]
+  pub file: String,
+
+  /// Only replay these request ids
+  #[arg(long)]
+  pub request_id: Vec<String>,
+
+  /// Only replay failed requests
+  #[arg(long)]
+  pub failures_only: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportCommand {
+  /// Import every operation of an OpenAPI 3 or Swagger 2 document, in json or yaml
+  Openapi {
+    /// The OpenAPI document
+    spec: String,
+
+    #[command(flatten)]
+    args: ImportArgs,
+  },
+  /// Import the requests of a HAR capture, e.g. recorded with browser developer tools
+  Har {
+    /// The HAR file
+    file: String,
+
+    /// Only import requests to this host or its subdomains, can be repeated
+    #[arg(long)]
+    filter_host: Vec<String>,
+
+    /// Only import requests whose response content type contains this, e.g. json, can be repeated
+    #[arg(long)]
+    filter_content_type: Vec<String>,
+
+    #[command(flatten)]
+    args: ImportArgs,
+  },
+  /// Import a curl command with the values to fuzz marked as §value§
+  Curl {
+    /// The file with the command, - to read it from stdin
+    file: String,
+
+    #[command(flatten)]
+    args: ImportArgs,
+  },
+  /// Import a raw http request, e.g. copied from a proxy, with the values to fuzz marked as §value§
+  Raw {
+    /// The file with the request, - to read it from stdin
+    file: String,
+
+    /// Scheme to send the request with when its target is a path
+    #[arg(long, default_value = "https")]
+    scheme: String,
+
+    #[command(flatten)]
+    args: ImportArgs,
+  },
 }
 
-impl CliFuzzEndpoint {
-  pub fn from_slice(args: &[&str]) -> Result<Self, structopt::clap::Error> {
-    CliFuzzEndpoint::from_iter_safe(args)
+/// What to do with an imported input, which is printed when it is neither saved nor run.
+#[derive(Debug, Args)]
+pub struct ImportArgs {
+  /// Save the generated input to this file so it can be edited before running
+  #[arg(long)]
+  pub save: Option<String>,
+
+  /// Fuzz the generated input right away
+  #[arg(long)]
+  pub run: bool,
+
+  /// Host to fuzz instead of the one in the imported document, e.g. http://localhost:8080
+  #[arg(long)]
+  pub host: Option<String>,
+
+  #[command(flatten)]
+  pub settings: SettingsArgs,
+}
+
+impl Cli {
+  pub fn from_slice(args: &[&str]) -> Result<Self, clap::Error> {
+    Cli::try_parse_from(args)
   }
 
-  pub fn parse() -> Self {
-    CliFuzzEndpoint::from_args()
+  pub fn parse_args() -> Self {
+    Cli::parse()
+  }
+
+  pub fn log_level(&self) -> LevelFilter {
+    match (self.verbose, self.quiet) {
+      (0, 0) => LevelFilter::Info,
+      (1, _) => LevelFilter::Debug,
+      (_, 0) => LevelFilter::Trace,
+      (_, 1) => LevelFilter::Warn,
+      (_, 2) => LevelFilter::Error,
+      _ => LevelFilter::Off,
+    }
   }
 }
 
@@ -22,8 +279,108 @@ mod tests {
 
   #[test]
   fn test_parse_input_file() {
-    let parsed = CliFuzzEndpoint::from_slice(&["fuzzy", "-i", "some_file.txt"]).unwrap();
+    let parsed = Cli::from_slice(&["fuzzy", "run", "-i", "some_file.txt"]).unwrap();
+
+    match parsed.command {
+      Command::Run(args) => {
+        assert_eq!(args.input_file, "some_file.txt");
+        assert_eq!(args.settings.to_settings(), Settings::default());
+      }
+      command => panic!("expected run, got {:?}", command),
+    }
+  }
 
-    assert_eq!(parsed.input_file, "some_file.txt");
+  #[test]
+  fn test_parse_run_settings() {
+    let parsed = Cli::from_slice(&[
+      "fuzzy", "run", "-i", "input.yml", "-d", "./words", "-o", "out.jsonl", "--format", "jsonl",
+      "-c", "20", "--channel-size", "64", "--metrics-interval", "1", "--request-id-header", "X-Id",
+      "--baseline-requests", "3", "--anomaly-threshold", "2.5", "--delay-factor", "3",
+      "--rate-limit", "50", "--host-rate-limit", "10", "--burst", "5", "--jitter-ms", "200",
+      "--include", "tag:sqli", "--include", "numericStrings", "--exclude", "file:strings",
+    ]).unwrap();
+
+    match parsed.command {
+      Command::Run(args) => {
+        assert_eq!(args.settings.to_settings(), Settings {
+          dictionary_dir: Some("./words".into()),
+          output: Some("out.jsonl".into()),
+          format: Some(OutputFormat::Jsonl),
+          concurrency: Some(20),
+          channel_size: Some(64),
+          metrics_interval: Some(1),
+          baseline_requests: Some(3),
+          anomaly_threshold: Some(2.5),
+          delay_factor: Some(3.0),
+          rate_limit: Some(50.0),
+          host_rate_limit: Some(10.0),
+          burst: Some(5),
+          jitter_ms: Some(200),
+          include: Some(vec!["tag:sqli".into(), "numericStrings".into()]),
+          exclude: Some(vec!["file:strings".into()]),
+        });
+        assert_eq!(args.request_id_header, Some("X-Id".into()));
+      }
+      command => panic!("expected run, got {:?}", command),
+    }
+  }
+
+  #[test]
+  fn test_parse_rejects_zero_sizes() {
+    assert!(Cli::from_slice(&["fuzzy", "run", "-i", "input.yml", "-c", "0"]).is_err());
+    assert!(Cli::from_slice(&["fuzzy", "run", "-i", "input.yml", "--channel-size", "0"]).is_err());
+    assert!(Cli::from_slice(&["fuzzy", "run", "-i", "input.yml", "--metrics-interval", "0"]).is_err());
+  }
+
+  #[test]
+  fn test_parse_subcommands() {
+    assert!(matches!(Cli::from_slice(&["fuzzy", "validate", "-i", "input.yml"]).unwrap().command, Command::Validate(_)));
+    assert!(matches!(Cli::from_slice(&["fuzzy", "dict", "list"]).unwrap().command, Command::Dict { command: DictCommand::List { .. } }));
+    assert!(matches!(Cli::from_slice(&["fuzzy", "replay", "-f", "out.jsonl", "--failures-only"]).unwrap().command, Command::Replay(_)));
+    assert!(Cli::from_slice(&["fuzzy", "-i", "input.yml"]).is_err());
+  }
+
+  #[test]
+  fn test_parse_import() {
+    let parsed = Cli::from_slice(&["fuzzy", "import", "openapi", "spec.yml", "--save", "input.yml", "--run", "-c", "5"]).unwrap();
+
+    match parsed.command {
+      Command::Import { command: ImportCommand::Openapi { spec, args } } => {
+        assert_eq!(spec, "spec.yml");
+        assert_eq!(args.save, Some("input.yml".into()));
+        assert!(args.run);
+        assert_eq!(args.settings.concurrency, Some(5));
+      }
+      command => panic!("expected import, got {:?}", command),
+    }
+
+    let parsed = Cli::from_slice(&["fuzzy", "import", "har", "capture.har", "--filter-host", "a.com", "--filter-host", "b.com"]).unwrap();
+    match parsed.command {
+      Command::Import { command: ImportCommand::Har { filter_host, filter_content_type, .. } } => {
+        assert_eq!(filter_host, vec!["a.com", "b.com"]);
+        assert!(filter_content_type.is_empty());
+      }
+      command => panic!("expected import, got {:?}", command),
+    }
+
+    let parsed = Cli::from_slice(&["fuzzy", "import", "raw", "-", "--scheme", "http"]).unwrap();
+    match parsed.command {
+      Command::Import { command: ImportCommand::Raw { file, scheme, .. } } => {
+        assert_eq!(file, "-");
+        assert_eq!(scheme, "http");
+      }
+      command => panic!("expected import, got {:?}", command),
+    }
+
+    let parsed = Cli::from_slice(&["fuzzy", "import", "curl", "command.txt"]).unwrap();
+    assert!(matches!(parsed.command, Command::Import { command: ImportCommand::Curl { .. } }));
+  }
+
+  #[test]
+  fn test_log_level() {
+    assert_eq!(Cli::from_slice(&["fuzzy", "dict", "list"]).unwrap().log_level(), LevelFilter::Info);
+    assert_eq!(Cli::from_slice(&["fuzzy", "-v", "dict", "list"]).unwrap().log_level(), LevelFilter::Debug);
+    assert_eq!(Cli::from_slice(&["fuzzy", "dict", "list", "-vv"]).unwrap().log_level(), LevelFilter::Trace);
+    assert_eq!(Cli::from_slice(&["fuzzy", "-qq", "dict", "list"]).unwrap().log_level(), LevelFilter::Error);
   }
 }
@@ -0,0 +1,18 @@
+use crate::domain::dictionary::FuzzyDictionary;
+use crate::domain::selector::Selection;
+use crate::error::FuzzyError;
+
+pub fn list(dictionary_dir: &str, selection: &Selection) -> Result<(), FuzzyError> {
+  let dictionary = FuzzyDictionary::load_from_dir(dictionary_dir)?;
+
+  for data in dictionary.data().iter().filter(|data| selection.selects(data)) {
+    let severity = data.severity().map(|severity| format!(" [{:?}]", severity).to_lowercase()).unwrap_or_default();
+    println!("{} ({} values){}: {}", data.id(), data.values().len(), severity, data.description());
+  }
+
+  for signature in dictionary.signatures() {
+    println!("signature {} ({} patterns): {}", signature.id(), signature.patterns().len(), signature.description());
+  }
+
+  Ok(())
+}
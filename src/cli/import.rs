@@ -0,0 +1,60 @@
+use std::fs::{read_to_string, write};
+use std::io::{stdin, Read};
+use crate::cli::cli::{ImportArgs, ImportCommand};
+use crate::cli::run::run_input;
+use crate::domain::input::FuzzyInput;
+use crate::error::FuzzyError;
+use crate::import::har::{self, HarFilter};
+use crate::import::{curl, openapi, raw};
+
+pub async fn import(command: &ImportCommand) -> Result<(), FuzzyError> {
+  let (input_data, args) = match command {
+    ImportCommand::Openapi { spec, args } => {
+      let content = read(spec)?;
+      (openapi::import(spec, &content)?, args)
+    }
+    ImportCommand::Har { file, filter_host, filter_content_type, args } => {
+      let content = read(file)?;
+      let filter = HarFilter { hosts: filter_host.clone(), content_types: filter_content_type.clone() };
+      (har::import(file, &content, &filter)?, args)
+    }
+    ImportCommand::Curl { file, args } => (curl::import(file, &read(file)?)?, args),
+    ImportCommand::Raw { file, scheme, args } => (raw::import(file, &read(file)?, scheme)?, args),
+  };
+
+  log::info!("imported {} paths", input_data.paths().len());
+  output(input_data, args).await
+}
+
+/// Reads a file to import, or stdin for `-`.
+fn read(file: &str) -> Result<String, FuzzyError> {
+  if file == "-" {
+    let mut content = String::new();
+    stdin().read_to_string(&mut content).map_err(|err| FuzzyError::io("stdin", err))?;
+    Ok(content)
+  } else {
+    read_to_string(file).map_err(|err| FuzzyError::io(file, err))
+  }
+}
+
+/// Saves, prints or runs an imported input.
+async fn output(mut input_data: FuzzyInput, args: &ImportArgs) -> Result<(), FuzzyError> {
+  if let Some(host) = &args.host {
+    input_data = input_data.with_host(host.trim_end_matches('/').to_string());
+  }
+
+  if let Some(file) = &args.save {
+    let yaml = serde_yaml::to_string(&input_data).map_err(|err| FuzzyError::yaml(file, err))?;
+    write(file, yaml).map_err(|err| FuzzyError::io(file, err))?;
+    log::info!("saved input to {}", file);
+  } else if !args.run {
+    let yaml = serde_yaml::to_string(&input_data).map_err(|err| FuzzyError::yaml("stdout", err))?;
+    print!("{}", yaml);
+  }
+
+  if args.run {
+    run_input(input_data, &args.settings.to_settings()).await?;
+  }
+
+  Ok(())
+}
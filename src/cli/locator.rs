@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fmt;
+use yaml_rust2::parser::{Event, MarkedEventReceiver, Parser};
+use yaml_rust2::scanner::Marker;
+
+/// A 1-based line and column in a yaml file.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Location {
+  line: usize,
+  column: usize,
+}
+
+impl Location {
+  pub fn new(line: usize, column: usize) -> Self {
+    Self { line, column }
+  }
+
+  pub fn line(&self) -> usize {
+    self.line
+  }
+
+  pub fn column(&self) -> usize {
+    self.column
+  }
+}
+
+impl Default for Location {
+  fn default() -> Self {
+    Location::new(1, 1)
+  }
+}
+
+impl fmt::Display for Location {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}", self.line, self.column)
+  }
+}
+
+/// Finds where a node is written in a yaml document from its dotted path, e.g. `paths.0.headers.1.name`.
+/// Mapping entries are located at their key and sequence items at their first character.
+#[derive(Debug, Default)]
+pub struct YamlLocator {
+  locations: HashMap<String, Location>,
+}
+
+impl YamlLocator {
+  pub fn new(content: &str) -> Self {
+    let mut receiver = LocationReceiver::default();
+    // an unparsable document keeps the locations read so far, serde reports the syntax error itself
+    let _ = Parser::new_from_str(content).load(&mut receiver, false);
+
+    Self { locations: receiver.locations }
+  }
+
+  /// The location of a path, or of its closest located parent when the path is not in the document.
+  pub fn locate(&self, path: &str) -> Location {
+    let mut path = path;
+
+    loop {
+      if let Some(location) = self.locations.get(path) {
+        return *location;
+      }
+
+      match path.rfind('.') {
+        Some(index) => path = &path[..index],
+        None => return Location::default(),
+      }
+    }
+  }
+}
+
+#[derive(Debug)]
+enum Frame {
+  Sequence { path: String, next_index: usize },
+  Mapping { path: String, key: Option<String> },
+}
+
+#[derive(Debug, Default)]
+struct LocationReceiver {
+  stack: Vec<Frame>,
+  locations: HashMap<String, Location>,
+}
+
+impl LocationReceiver {
+  /// The path of the node that starts at `mark`, recording its location when it is a key or a sequence item.
+  fn enter_node(&mut self, mark: &Marker, is_mapping: bool) -> Option<String> {
+    let location = location(mark);
+
+    match self.stack.last_mut() {
+      None => Some(String::new()),
+      Some(Frame::Sequence { path, next_index }) => {
+        let child = join(path, &next_index.to_string());
+        *next_index += 1;
+        // a mapping starts after its first key, so mapping items are located at that key instead
+        if !is_mapping {
+          self.locations.insert(child.clone(), location);
+        }
+        Some(child)
+      }
+      Some(Frame::Mapping { path, key }) => key.take().map(|key| join(path, &key)),
+    }
+  }
+}
+
+impl MarkedEventReceiver for LocationReceiver {
+  fn on_event(&mut self, event: Event, mark: Marker) {
+    match event {
+      // a scalar where a mapping expects a key is the key itself
+      Event::Scalar(value, ..) if self.enter_node(&mark, false).is_none() => {
+        if let Some(Frame::Mapping { path, key }) = self.stack.last_mut() {
+          self.locations.entry(path.clone()).or_insert(location(&mark));
+          self.locations.insert(join(path, &value), location(&mark));
+          *key = Some(value);
+        }
+      }
+      Event::Alias(_) => {
+        self.enter_node(&mark, false);
+      }
+      Event::SequenceStart(..) => {
+        let path = self.enter_node(&mark, false).unwrap_or_default();
+        self.stack.push(Frame::Sequence { path, next_index: 0 });
+      }
+      Event::MappingStart(..) => {
+        let path = self.enter_node(&mark, true).unwrap_or_default();
+        self.stack.push(Frame::Mapping { path, key: None });
+      }
+      Event::SequenceEnd | Event::MappingEnd => {
+        self.stack.pop();
+      }
+      _ => {}
+    }
+  }
+}
+
+/// Markers count columns from 0.
+fn location(mark: &Marker) -> Location {
+  Location::new(mark.line(), mark.col() + 1)
+}
+
+fn join(parent: &str, child: &str) -> String {
+  if parent.is_empty() {
+    child.to_string()
+  } else {
+    format!("{}.{}", parent, child)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DOCUMENT: &str = r#"host: http://test.com
+paths:
+  - endpoint: /test
+    headers:
+      - name: Accept
+        value: application/json
+      - { name: "Bad Header", value: x }
+    fuzz_points: [a, b]
+"#;
+
+  #[test]
+  fn test_locate_keys_and_items() {
+    let locator = YamlLocator::new(DOCUMENT);
+
+    assert_eq!(locator.locate("host"), Location::new(1, 1));
+    assert_eq!(locator.locate("paths.0"), Location::new(3, 5));
+    assert_eq!(locator.locate("paths.0.headers.0.value"), Location::new(6, 9));
+    assert_eq!(locator.locate("paths.0.headers.1.name"), Location::new(7, 11));
+    assert_eq!(locator.locate("paths.0.fuzz_points.1"), Location::new(8, 22));
+  }
+
+  #[test]
+  fn test_locate_falls_back_to_parent() {
+    let locator = YamlLocator::new(DOCUMENT);
+
+    assert_eq!(locator.locate("paths.0.unknown.field"), Location::new(3, 5));
+    assert_eq!(locator.locate("missing"), Location::default());
+  }
+}
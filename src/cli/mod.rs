@@ -1 +1,8 @@
-pub mod cli;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod cli;
+pub mod dict;
+pub mod import;
+pub mod locator;
+pub mod replay;
+pub mod run;
+pub mod validate;
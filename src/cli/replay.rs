@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::fs::read_to_string;
+use std::time::Instant;
+use reqwest::Client;
+use reqwest::header::HeaderMap;
+use serde::Deserialize;
+use crate::cli::cli::ReplayArgs;
+use crate::domain::input::HttpMethod;
+use crate::error::{FuzzyError, RequestError};
+use crate::fuzzer::fuzzy::{header_map, send_request};
+
+/// The parts of a json lines output record needed to send its request again.
+#[derive(Debug, Deserialize)]
+struct ReplayRecord {
+  request_id: String,
+  outcome: String,
+  method: HttpMethod,
+  url: String,
+  request_headers: BTreeMap<String, String>,
+  request_body: String,
+  status: Option<u16>,
+}
+
+impl ReplayRecord {
+  fn selected(&self, args: &ReplayArgs) -> bool {
+    let by_id = args.request_id.is_empty() || args.request_id.contains(&self.request_id);
+    let by_outcome = !args.failures_only || self.outcome == "failure";
+
+    by_id && by_outcome
+  }
+
+  fn headers(&self) -> Result<HeaderMap, RequestError> {
+    header_map(&self.request_headers)
+  }
+}
+
+/// Sends the selected requests of an output file one at a time and prints how their status compares.
+/// Returns whether every record could be read, records that cannot be sent are reported and skipped.
+pub async fn replay(args: &ReplayArgs) -> Result<bool, FuzzyError> {
+  let content = read_to_string(&args.file).map_err(|err| FuzzyError::io(&args.file, err))?;
+
+  let client = Client::new();
+  let mut ok = true;
+
+  for (index, line) in content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+    let record: ReplayRecord = match serde_json::from_str(line) {
+      Ok(record) => record,
+      Err(err) => {
+        println!("{}:{}: {}", args.file, index + 1, err);
+        ok = false;
+        continue;
+      }
+    };
+
+    if !record.selected(args) {
+      continue;
+    }
+
+    let headers = match record.headers() {
+      Ok(headers) => headers,
+      Err(err) => {
+        println!("{} could not build request: {}", record.request_id, err);
+        continue;
+      }
+    };
+
+    let started = Instant::now();
+    match send_request(&client, &record.method, &record.url, headers, record.request_body.clone()).await {
+      Ok(response) => println!(
+        "{} {:?} {} -> {} in {:.0} ms (recorded {})",
+        record.request_id,
+        record.method,
+        record.url,
+        response.status().as_u16(),
+        started.elapsed().as_secs_f64() * 1000.0,
+        record.status.map(|status| status.to_string()).unwrap_or_else(|| "none".into())
+      ),
+      Err(err) => println!("{} {:?} {} -> {}", record.request_id, record.method, record.url, err),
+    }
+  }
+
+  Ok(ok)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn sample_record() -> ReplayRecord {
+    serde_json::from_str(r#"{
+      "request_id": "6f1c", "outcome": "failure", "method": "POST", "url": "http://test.com/test",
+      "request_headers": { "Accept": "application/json" }, "request_body": "{}", "payloads": [],
+      "status": 500, "latency_ms": 1.5
+    }"#).unwrap()
+  }
+
+  fn sample_args(request_id: Vec<String>, failures_only: bool) -> ReplayArgs {
+    ReplayArgs { file: "out.jsonl".into(), request_id, failures_only }
+  }
+
+  #[test]
+  fn test_record_deserialization() {
+    let record = sample_record();
+
+    assert_eq!(record.method, HttpMethod::POST);
+    assert_eq!(record.status, Some(500));
+    assert_eq!(record.headers().unwrap().get("Accept").unwrap(), "application/json");
+  }
+
+  #[test]
+  fn test_record_selection() {
+    let record = sample_record();
+
+    assert!(record.selected(&sample_args(vec![], false)));
+    assert!(record.selected(&sample_args(vec![], true)));
+    assert!(record.selected(&sample_args(vec!["6f1c".into()], true)));
+    assert!(!record.selected(&sample_args(vec!["other".into()], false)));
+  }
+}
@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use crate::cli::cli::RunArgs;
+use crate::domain::dictionary::FuzzyDictionary;
+use crate::domain::input::FuzzyInput;
+use crate::domain::selector::Selection;
+use crate::domain::settings::Settings;
+use crate::error::FuzzyError;
+use crate::fuzzer::data_channels::FuzzyResponseChannel;
+use crate::fuzzer::fuzzy::Fuzzer;
+use crate::fuzzer::metrics::Metrics;
+use crate::fuzzer::rate_limit::RateLimiter;
+use crate::fuzzer::result_aggregator::ResultAggregator;
+
+pub async fn run(args: &RunArgs) -> Result<(), FuzzyError> {
+  let input_file_str = std::fs::read_to_string(&args.input_file)
+    .map_err(|err| FuzzyError::io(&args.input_file, err))?;
+  let mut input_data: FuzzyInput = serde_yaml::from_str(&input_file_str)
+    .map_err(|err| FuzzyError::yaml(&args.input_file, err))?;
+
+  if args.request_id_header.is_some() {
+    input_data = input_data.with_request_id_header(args.request_id_header.clone());
+  }
+
+  run_input(input_data, &args.settings.to_settings()).await
+}
+
+/// Loads the dictionaries and fuzzes an input, with `overrides` taking precedence over its settings.
+pub async fn run_input(input_data: FuzzyInput, overrides: &Settings) -> Result<(), FuzzyError> {
+  let settings = input_data.settings().override_with(overrides);
+  settings.check()?;
+  let dictionary = FuzzyDictionary::load_from_dir(settings.dictionary_dir())?;
+
+  log::info!("loaded...");
+
+  run_fuzzer(input_data, dictionary, &settings).await
+}
+
+/// Fuzzes every path of the input, reporting metrics until all results have been written.
+pub async fn run_fuzzer(input_data: FuzzyInput, dictionary: FuzzyDictionary, settings: &Settings) -> Result<(), FuzzyError> {
+  let finished = Arc::new(AtomicBool::new(false));
+  let fuzzer_finished = finished.clone();
+
+  let response_channel = FuzzyResponseChannel::new(settings.channel_size());
+
+  let shared_metrics = Metrics::new();
+  let shared_metrics_clone = shared_metrics.clone();
+
+  let fuzzer = Fuzzer::new(settings.concurrency(), response_channel.sender())
+    .with_baseline(settings.baseline_requests(), settings.anomaly_threshold())
+    .with_delay_factor(settings.delay_factor())
+    .with_selection(Selection::new(settings.include(), settings.exclude()))
+    .with_rate_limiter(
+      RateLimiter::new(settings.rate_limit(), settings.host_rate_limit(), settings.burst())
+        .with_jitter(Duration::from_millis(settings.jitter_ms()))
+    );
+  let mut aggregator = ResultAggregator::new(response_channel.receiver(), Some(settings.output()), shared_metrics.clone()).await?
+    .with_format(settings.format());
+
+  let aggregator_task = tokio::spawn(async move {
+    aggregator.process_results().await
+  });
+
+  let metrics_interval = settings.metrics_interval();
+  let display_task = tokio::spawn(async move {
+    while !finished.load(Ordering::Relaxed) {
+      // reading metrics into a local variable to release the lock before sleeping
+      // this prevents lock from blocking the fuzzer thread
+      let local_metrics = {
+        let metrics = shared_metrics.read().await;
+        *metrics
+      };
+
+      local_metrics.display();
+      tokio::time::sleep(tokio::time::Duration::from_secs(metrics_interval)).await;
+    }
+  });
+
+  let fuzzer_task = tokio::spawn(async move {
+    log::info!("fuzzing...");
+    let result = fuzzer.fuzz(&input_data, Arc::new(dictionary)).await;
+    fuzzer_finished.store(true, Ordering::Relaxed);
+    log::info!("fuzzing finished");
+    result
+  });
+
+  let (aggregator_res, display_res, fuzzer_res) = tokio::try_join!(aggregator_task, display_task, fuzzer_task)?;
+  log::info!("all tasks joined...");
+  log::debug!("display task returned: {:?}", display_res);
+
+  let final_metrics = shared_metrics_clone.read().await;
+  final_metrics.display();
+
+  // an output error closes the channel, so it explains the fuzzer error that follows it
+  aggregator_res.and(fuzzer_res)
+}
@@ -0,0 +1,485 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::read_to_string;
+use std::str::FromStr;
+use regex::Regex;
+use reqwest::header::HeaderName;
+use serde::de::DeserializeOwned;
+use crate::cli::cli::ValidateArgs;
+use crate::cli::locator::{Location, YamlLocator};
+use crate::domain::dictionary::FuzzyDictionary;
+use crate::domain::input::{FuzzyInput, Path};
+use crate::domain::placeholder::contains_placeholder;
+use crate::domain::selector::Selector;
+use crate::domain::settings::DEFAULT_DICTIONARY_DIR;
+use crate::domain::wordlist::{dictionary_files, DictionaryFormat};
+use crate::error::FuzzyError;
+use crate::fuzzer::matcher::{MatcherError, ResponseMatcher};
+use crate::fuzzer::template::RequestTemplate;
+
+/// Something wrong in an input file or dictionary, printed as `file:line:column: message`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Problem {
+  file: String,
+  location: Location,
+  message: String,
+}
+
+impl Problem {
+  pub fn new(file: &str, location: Location, message: String) -> Self {
+    Self { file: file.to_string(), location, message }
+  }
+
+  pub fn file(&self) -> &String {
+    &self.file
+  }
+
+  pub fn location(&self) -> &Location {
+    &self.location
+  }
+
+  pub fn message(&self) -> &String {
+    &self.message
+  }
+}
+
+impl fmt::Display for Problem {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{}:{}: {}", self.file, self.location, self.message)
+  }
+}
+
+/// A yaml file being validated, collecting problems located by their path in the document.
+struct YamlFile {
+  file: String,
+  locator: YamlLocator,
+  problems: Vec<Problem>,
+}
+
+impl YamlFile {
+  fn new(file: &str, content: &str) -> Self {
+    Self { file: file.to_string(), locator: YamlLocator::new(content), problems: Vec::new() }
+  }
+
+  fn report(&mut self, path: &str, message: String) {
+    let location = self.locator.locate(path);
+    self.problems.push(Problem::new(&self.file, location, message));
+  }
+
+  /// Deserializes the document, reporting unknown fields and the error that stopped deserialization.
+  fn parse<T: DeserializeOwned>(&mut self, content: &str) -> Option<T> {
+    let mut unknown_fields = Vec::new();
+    let deserializer = serde_yaml::Deserializer::from_str(content);
+    let result = serde_ignored::deserialize(deserializer, |path| {
+      unknown_fields.push(path.to_string());
+    });
+
+    for field in unknown_fields {
+      // options show up as `?` segments in the path of an ignored field
+      let path = field.split('.').filter(|segment| *segment != "?").collect::<Vec<_>>().join(".");
+      let name = path.rsplit('.').next().unwrap_or_default().to_string();
+      self.report(&path, format!("unknown field `{}`", name));
+    }
+
+    match result {
+      Ok(value) => Some(value),
+      Err(err) => {
+        let location = err.location()
+          .map(|location| Location::new(location.line(), location.column()))
+          .unwrap_or_default();
+        self.problems.push(Problem::new(&self.file, location, err.to_string()));
+        None
+      }
+    }
+  }
+}
+
+/// Checks an input file, using `dictionary` to resolve the ids its fuzz points include.
+pub fn validate_input(file: &str, content: &str, dictionary: &FuzzyDictionary) -> (Option<FuzzyInput>, Vec<Problem>) {
+  let mut yaml = YamlFile::new(file, content);
+  let input: Option<FuzzyInput> = yaml.parse(content);
+
+  if let Some(input) = &input {
+    for (field, message) in input.settings().problems() {
+      yaml.report(&format!("settings.{}", field), message);
+    }
+    validate_selectors(&mut yaml, "settings.include", input.settings().include(), dictionary);
+    validate_selectors(&mut yaml, "settings.exclude", input.settings().exclude(), dictionary);
+
+    for (index, path) in input.paths().iter().enumerate() {
+      validate_path(&mut yaml, &format!("paths.{}", index), path, input, dictionary);
+    }
+  }
+
+  (input, yaml.problems)
+}
+
+/// Reports the dictionary selectors that match no dictionary, e.g. a misspelled id or tag.
+fn validate_selectors(yaml: &mut YamlFile, at: &str, selectors: &[String], dictionary: &FuzzyDictionary) {
+  for (index, selector) in selectors.iter().enumerate() {
+    let parsed = Selector::parse(selector);
+    if dictionary.data().iter().any(|data| parsed.matches(data)) {
+      continue;
+    }
+
+    let message = match parsed {
+      Selector::Id(id) => format!("unknown dictionary id {}", id),
+      _ => format!("selector {} matches no dictionary", selector),
+    };
+    yaml.report(&format!("{}.{}", at, index), message);
+  }
+}
+
+fn validate_path(yaml: &mut YamlFile, at: &str, path: &Path, input: &FuzzyInput, dictionary: &FuzzyDictionary) {
+  for (index, header) in path.headers().iter().enumerate() {
+    if HeaderName::from_str(header.name()).is_err() {
+      yaml.report(&format!("{}.headers.{}.name", at, index), format!("invalid header name {:?}", header.name()));
+    }
+
+    if let Some(value) = header.value() {
+      if header.fuzz() && !contains_placeholder(value) {
+        yaml.report(&format!("{}.headers.{}.value", at, index),
+          format!("fuzzed header {} has no placeholder, its whole value will be replaced", header.name()));
+      }
+    }
+  }
+
+  for (index, header) in path.expected_headers().iter().enumerate() {
+    if HeaderName::from_str(header.name()).is_err() {
+      yaml.report(&format!("{}.expected_headers.{}.name", at, index), format!("invalid header name {:?}", header.name()));
+    }
+  }
+
+  for (index, parameter) in path.query_parameters().iter().enumerate() {
+    if let Some(value) = parameter.value() {
+      if parameter.fuzz() && !contains_placeholder(value) {
+        yaml.report(&format!("{}.query_parameters.{}.value", at, index),
+          format!("fuzzed query parameter {} has no placeholder, its whole value will be replaced", parameter.name()));
+      }
+    }
+  }
+
+  match ResponseMatcher::new(path) {
+    Err(err @ MatcherError::InvalidJson(_)) => yaml.report(&format!("{}.expected_body", at), err.to_string()),
+    Err(err) => yaml.report(at, err.to_string()),
+    Ok(_) => {}
+  }
+
+  let placeholders = match path.to_url(input.host(), input.base_path()) {
+    Ok(url) => RequestTemplate::new(&url, path).placeholders(),
+    Err(err) => {
+      yaml.report(&format!("{}.endpoint", at), format!("invalid url: {}", err));
+      return;
+    }
+  };
+
+  if placeholders.is_empty() {
+    yaml.report(&format!("{}.endpoint", at), format!("{} has nothing to fuzz, it has no placeholders", path.endpoint()));
+  }
+
+  validate_selectors(yaml, &format!("{}.include", at), path.include(), dictionary);
+  validate_selectors(yaml, &format!("{}.exclude", at), path.exclude(), dictionary);
+
+  for (index, point) in path.fuzz_points().iter().enumerate() {
+    if !placeholders.contains(point.name()) {
+      yaml.report(&format!("{}.fuzz_points.{}.name", at, index), format!("fuzz point {} is not used by any placeholder", point.name()));
+    }
+
+    validate_selectors(yaml, &format!("{}.fuzz_points.{}.include", at, index), point.include(), dictionary);
+    validate_selectors(yaml, &format!("{}.fuzz_points.{}.exclude", at, index), point.exclude(), dictionary);
+  }
+}
+
+/// A problem for an error reading a file, located at the file the error names.
+fn error_problem(file: &str, err: FuzzyError) -> Problem {
+  match err {
+    FuzzyError::Io { path, source } => Problem::new(&path, Location::default(), source.to_string()),
+    err => Problem::new(file, Location::default(), err.to_string()),
+  }
+}
+
+/// Checks every dictionary file under a directory, reporting ids already defined by an earlier file.
+pub fn validate_dictionaries(dir: &str) -> (FuzzyDictionary, Vec<Problem>) {
+  let mut problems = Vec::new();
+  let mut data = Vec::new();
+  let mut signatures = Vec::new();
+  let mut seen_ids: HashMap<String, (String, Location)> = HashMap::new();
+  let mut seen_signature_ids: HashMap<String, (String, Location)> = HashMap::new();
+
+  let files = match dictionary_files(dir) {
+    Ok(files) => files,
+    Err(err) => {
+      problems.push(error_problem(dir, err));
+      return (FuzzyDictionary::new(data), problems);
+    }
+  };
+
+  for dictionary_file in files {
+    let file = dictionary_file.path().display().to_string();
+    if dictionary_file.format() == DictionaryFormat::Wordlist {
+      let wordlist = match dictionary_file.read_bytes() {
+        Ok(content) => dictionary_file.to_wordlist(&content),
+        Err(err) => {
+          problems.push(error_problem(&file, err));
+          continue;
+        }
+      };
+      match seen_ids.get(wordlist.id()) {
+        Some((first_file, first_location)) => {
+          let message = format!("duplicate dictionary id {}, first defined at {}:{}", wordlist.id(), first_file, first_location);
+          problems.push(Problem::new(&file, Location::default(), message));
+        }
+        None => {
+          seen_ids.insert(wordlist.id().clone(), (file.clone(), Location::default()));
+        }
+      }
+      if wordlist.values().is_empty() {
+        problems.push(Problem::new(&file, Location::default(), format!("wordlist {} has no values", wordlist.id())));
+      }
+
+      data.push(wordlist);
+      continue;
+    }
+
+    let content = match dictionary_file.read() {
+      Ok(content) => content,
+      Err(err) => {
+        problems.push(error_problem(&file, err));
+        continue;
+      }
+    };
+
+    let mut yaml = YamlFile::new(&file, &content);
+    if let Some(dictionary) = yaml.parse::<FuzzyDictionary>(&content) {
+      for (index, entry) in dictionary.data().iter().enumerate() {
+        let id_path = format!("data.{}.id", index);
+
+        match seen_ids.get(entry.id()) {
+          Some((first_file, first_location)) => {
+            let message = format!("duplicate dictionary id {}, first defined at {}:{}", entry.id(), first_file, first_location);
+            yaml.report(&id_path, message);
+          }
+          None => {
+            seen_ids.insert(entry.id().clone(), (file.clone(), yaml.locator.locate(&id_path)));
+          }
+        }
+
+        if entry.values().is_empty() {
+          yaml.report(&format!("data.{}.values", index), format!("dictionary {} has no values", entry.id()));
+        }
+      }
+
+      for (index, signature) in dictionary.signatures().iter().enumerate() {
+        let id_path = format!("signatures.{}.id", index);
+
+        match seen_signature_ids.get(signature.id()) {
+          Some((first_file, first_location)) => {
+            let message = format!("duplicate signature id {}, first defined at {}:{}", signature.id(), first_file, first_location);
+            yaml.report(&id_path, message);
+          }
+          None => {
+            seen_signature_ids.insert(signature.id().clone(), (file.clone(), yaml.locator.locate(&id_path)));
+          }
+        }
+
+        if signature.patterns().is_empty() {
+          yaml.report(&format!("signatures.{}.patterns", index), format!("signature {} has no patterns", signature.id()));
+        }
+        for (pattern_index, pattern) in signature.patterns().iter().enumerate() {
+          if let Err(err) = Regex::new(pattern) {
+            yaml.report(&format!("signatures.{}.patterns.{}", index, pattern_index), format!("invalid pattern: {}", err));
+          }
+        }
+      }
+
+      data.extend(dictionary.data().iter().map(|entry| entry.clone().with_file(dictionary_file.name().clone())));
+      signatures.extend(dictionary.signatures().clone());
+    }
+
+    problems.extend(yaml.problems);
+  }
+
+  (FuzzyDictionary::new(data).with_signatures(signatures), problems)
+}
+
+/// Prints every problem found in the input file and dictionaries, returning whether there were none.
+pub fn validate(args: &ValidateArgs) -> bool {
+  let content = match read_to_string(&args.input_file) {
+    Ok(content) => content,
+    Err(err) => {
+      println!("{}", Problem::new(&args.input_file, Location::default(), err.to_string()));
+      return false;
+    }
+  };
+
+  // the dictionary directory may come from the input settings, which can only be read once the input parses
+  let settings_dir = serde_yaml::from_str::<FuzzyInput>(&content).ok()
+    .and_then(|input| input.settings().dictionary_dir.clone());
+  let dictionary_dir = args.dictionary_dir.clone()
+    .or(settings_dir)
+    .unwrap_or_else(|| DEFAULT_DICTIONARY_DIR.to_string());
+
+  let (dictionary, mut problems) = validate_dictionaries(&dictionary_dir);
+  let (_, input_problems) = validate_input(&args.input_file, &content, &dictionary);
+  problems.extend(input_problems);
+
+  for problem in &problems {
+    println!("{}", problem);
+  }
+
+  if problems.is_empty() {
+    println!("{} and {} are valid", args.input_file, dictionary_dir);
+  } else {
+    println!("found {} problem(s)", problems.len());
+  }
+
+  problems.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dictionary() -> FuzzyDictionary {
+    serde_yaml::from_str(r#"
+data:
+  - id: known
+    description: known values
+    values: ["a"]
+"#).unwrap()
+  }
+
+  fn messages(problems: &[Problem]) -> Vec<String> {
+    problems.iter().map(|problem| problem.to_string()).collect()
+  }
+
+  #[test]
+  fn test_valid_input() {
+    let content = std::fs::read_to_string("input.yml").unwrap();
+    let (input, problems) = validate_input("input.yml", &content, &dictionary());
+
+    assert!(input.is_some());
+    assert_eq!(messages(&problems), Vec::<String>::new());
+  }
+
+  #[test]
+  fn test_reports_every_problem_with_location() {
+    let content = r#"host: http://test.com
+base_path: /api
+paths:
+  - endpoint: /users/{fuzz_id}
+    method: GET
+    expected_status: 200
+    expected_headers: []
+    expected_body: ""
+    query_parameters:
+      - name: filter
+        fuzz: true
+        value: plain
+    headers:
+      - name: Bad Header
+        value: x
+        fuzz: false
+        colour: red
+    body: ""
+    fuzz_points:
+      - name: fuzz_id
+        include: [known, missing]
+        exclude: ["tag:misspelled"]
+      - name: unused
+"#;
+    let (input, problems) = validate_input("input.yml", content, &dictionary());
+
+    assert!(input.is_some());
+    assert_eq!(messages(&problems), vec![
+      "input.yml:17:9: unknown field `colour`",
+      "input.yml:14:9: invalid header name \"Bad Header\"",
+      "input.yml:12:9: fuzzed query parameter filter has no placeholder, its whole value will be replaced",
+      "input.yml:21:26: unknown dictionary id missing",
+      "input.yml:22:19: selector tag:misspelled matches no dictionary",
+      "input.yml:23:9: fuzz point unused is not used by any placeholder",
+    ]);
+  }
+
+  #[test]
+  fn test_reports_invalid_settings() {
+    let content = "host: http://test.com\nbase_path: /api\nsettings:\n  concurrency: 0\npaths: []\n";
+    let (_, problems) = validate_input("input.yml", content, &dictionary());
+
+    assert_eq!(messages(&problems), vec!["input.yml:4:3: concurrency must be at least 1"]);
+  }
+
+  #[test]
+  fn test_reports_deserialization_error() {
+    let content = "host: http://test.com\nbase_path: /api\npaths:\n  - endpoint: /test\n    method: FETCH\n";
+    let (input, problems) = validate_input("input.yml", content, &dictionary());
+
+    assert!(input.is_none());
+    assert_eq!(problems.len(), 1);
+    assert_eq!(problems[0].location(), &Location::new(5, 13));
+  }
+
+  #[test]
+  fn test_validate_dictionaries() {
+    let dir = std::env::temp_dir().join(format!("fuzzy-validate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.yml"), "data:\n  - id: one\n    description: first\n    values: [\"1\"]\n").unwrap();
+    std::fs::write(dir.join("b.yml"), "data:\n  - id: two\n    description: empty\n    values: []\n  - id: one\n    description: again\n    values: [\"1\"]\n").unwrap();
+
+    let (dictionary, problems) = validate_dictionaries(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let a = dir.join("a.yml").display().to_string();
+    let b = dir.join("b.yml").display().to_string();
+    assert_eq!(dictionary.data().len(), 3);
+    assert_eq!(messages(&problems), vec![
+      format!("{}:4:5: dictionary two has no values", b),
+      format!("{}:5:5: duplicate dictionary id one, first defined at {}:2:5", b, a),
+    ]);
+  }
+
+  #[test]
+  fn test_validate_signatures() {
+    let dir = std::env::temp_dir().join(format!("fuzzy-validate-signatures-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("a.yml"), "signatures:\n  - id: sqlError\n    description: sql\n    patterns:\n      - \"SQLSTATE\\\\[\"\n      - \"(unclosed\"\n").unwrap();
+    std::fs::write(dir.join("b.yml"), "signatures:\n  - id: sqlError\n    description: again\n    patterns: []\n").unwrap();
+
+    let (dictionary, problems) = validate_dictionaries(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let a = dir.join("a.yml").display().to_string();
+    let b = dir.join("b.yml").display().to_string();
+    assert_eq!(dictionary.signatures().len(), 2);
+    assert_eq!(problems.len(), 3);
+    assert!(messages(&problems)[0].starts_with(&format!("{}:6:9: invalid pattern: ", a)));
+    assert_eq!(messages(&problems)[1..], [
+      format!("{}:2:5: duplicate signature id sqlError, first defined at {}:2:5", b, a),
+      format!("{}:4:5: signature sqlError has no patterns", b),
+    ]);
+  }
+
+  #[test]
+  fn test_validate_wordlists() {
+    let dir = std::env::temp_dir().join(format!("fuzzy-validate-wordlists-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("empty.txt"), "# nothing yet\n").unwrap();
+    std::fs::write(dir.join("names.txt"), "alice\nbob\n").unwrap();
+    std::fs::write(dir.join("packed.txt.gz"), "not gzip").unwrap();
+
+    let (dictionary, problems) = validate_dictionaries(dir.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(dictionary.data().len(), 2);
+    assert_eq!(problems.len(), 2);
+    assert_eq!(problems[0].to_string(), format!("{}:1:1: wordlist empty has no values", dir.join("empty.txt").display()));
+    assert!(problems[1].to_string().starts_with(&format!("{}:1:1: ", dir.join("packed.txt.gz").display())));
+  }
+
+  #[test]
+  fn test_bundled_dictionaries_are_valid() {
+    let (_, problems) = validate_dictionaries(DEFAULT_DICTIONARY_DIR);
+
+    assert_eq!(messages(&problems), Vec::<String>::new());
+  }
+}
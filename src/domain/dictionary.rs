@@ -1,13 +1,48 @@
-use serde::Deserialize;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::domain::wordlist::{dictionary_files, DictionaryFormat};
+use crate::error::FuzzyError;
 
+/// Payloads in `data` and, optionally, error signatures that responses are searched for in `signatures`.
 #[derive(Clone, Debug, Deserialize)]
 pub struct FuzzyDictionary {
+  #[serde(default)]
   data: Vec<FuzzyData>,
+  #[serde(default)]
+  signatures: Vec<ErrorSignature>,
 }
 
 impl FuzzyDictionary {
   pub fn new(data: Vec<FuzzyData>) -> Self {
-    Self { data }
+    Self { data, signatures: Vec::new() }
+  }
+
+  pub fn with_signatures(mut self, signatures: Vec<ErrorSignature>) -> Self {
+    self.signatures = signatures;
+    self
+  }
+
+  /// Merges every dictionary file under a directory, payloads and signatures, into one dictionary. Yaml files have
+  /// to parse, while wordlists load whatever their encoding, with bytes that are not utf-8 replaced.
+  pub fn load_from_dir<P: AsRef<Path>>(dir_path: P) -> Result<FuzzyDictionary, FuzzyError> {
+    let mut dictionaries = Vec::new();
+    let mut signatures = Vec::new();
+
+    for file in dictionary_files(dir_path)? {
+      match file.format() {
+        DictionaryFormat::Yaml => {
+          let content = file.read()?;
+          let dict: FuzzyDictionary = serde_yaml::from_str(&content)
+            .map_err(|err| FuzzyError::yaml(&file.path().display().to_string(), err))?;
+
+          dictionaries.extend(dict.data().iter().map(|data| data.clone().with_file(file.name().clone())));
+          signatures.extend(dict.signatures().clone());
+        }
+        DictionaryFormat::Wordlist => dictionaries.push(file.to_wordlist(&file.read_bytes()?)),
+      }
+    }
+
+    Ok(FuzzyDictionary::new(dictionaries).with_signatures(signatures))
   }
 }
 
@@ -15,16 +50,138 @@ impl FuzzyDictionary {
 pub struct FuzzyData {
   id: String,
   description: String,
+  #[serde(default)]
+  cves: Vec<String>,
+  /// Free-form labels, e.g. `sqli` or `owasp-a03`.
+  #[serde(default)]
+  tags: Vec<String>,
+  #[serde(default)]
+  severity: Option<Severity>,
+  /// Links describing what the values probe for.
+  #[serde(default)]
+  references: Vec<String>,
   values: Vec<String>,
+  /// Path of the file the dictionary was loaded from, relative to the dictionary directory and without extensions.
+  #[serde(skip)]
+  file: String,
+}
+
+/// How serious a finding from a dictionary would be.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+  Info,
+  Low,
+  Medium,
+  High,
+  Critical,
+}
+
+/// What the dictionaries of a request probe for, carried from their values to its results.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Metadata {
+  cves: Vec<String>,
+  tags: Vec<String>,
+  severity: Option<Severity>,
+  references: Vec<String>,
+}
+
+impl Metadata {
+  pub fn new(cves: Vec<String>, tags: Vec<String>, severity: Option<Severity>, references: Vec<String>) -> Self {
+    Self { cves, tags, severity, references }
+  }
+
+  /// Adds the ids, tags and references not listed yet and keeps the highest severity.
+  pub fn merge(&mut self, other: &Metadata) {
+    for (merged, added) in [(&mut self.cves, &other.cves), (&mut self.tags, &other.tags), (&mut self.references, &other.references)] {
+      for value in added {
+        if !merged.contains(value) {
+          merged.push(value.clone());
+        }
+      }
+    }
+    self.severity = self.severity.max(other.severity);
+  }
+
+  pub fn cves(&self) -> &Vec<String> {
+    &self.cves
+  }
+
+  pub fn tags(&self) -> &Vec<String> {
+    &self.tags
+  }
+
+  pub fn severity(&self) -> Option<Severity> {
+    self.severity
+  }
+
+  pub fn references(&self) -> &Vec<String> {
+    &self.references
+  }
+}
+
+/// Text that gives away an error or leaked content, e.g. a SQL driver error or `/etc/passwd`, searched for in every
+/// response body whatever its status.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ErrorSignature {
+  id: String,
+  description: String,
+  /// Regular expressions, the signature matches when any of them does.
+  patterns: Vec<String>,
+}
+
+impl ErrorSignature {
+  pub fn new(id: String, description: String, patterns: Vec<String>) -> Self {
+    Self { id, description, patterns }
+  }
+
+  pub fn id(&self) -> &String {
+    &self.id
+  }
+
+  pub fn description(&self) -> &String {
+    &self.description
+  }
+
+  pub fn patterns(&self) -> &Vec<String> {
+    &self.patterns
+  }
 }
 
 impl FuzzyDictionary {
   pub fn data(&self) -> &Vec<FuzzyData> {
     &self.data
   }
+
+  pub fn signatures(&self) -> &Vec<ErrorSignature> {
+    &self.signatures
+  }
 }
 
 impl FuzzyData {
+  pub fn new(id: String, description: String, values: Vec<String>) -> Self {
+    Self {
+      id,
+      description,
+      cves: Vec::new(),
+      tags: Vec::new(),
+      severity: None,
+      references: Vec::new(),
+      values,
+      file: String::new(),
+    }
+  }
+
+  pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+    self.tags = tags;
+    self
+  }
+
+  pub fn with_file(mut self, file: String) -> Self {
+    self.file = file;
+    self
+  }
+
   pub fn id(&self) -> &String {
     &self.id
   }
@@ -33,9 +190,33 @@ impl FuzzyData {
     &self.description
   }
 
+  pub fn cves(&self) -> &Vec<String> {
+    &self.cves
+  }
+
+  pub fn tags(&self) -> &Vec<String> {
+    &self.tags
+  }
+
+  pub fn severity(&self) -> Option<Severity> {
+    self.severity
+  }
+
+  pub fn references(&self) -> &Vec<String> {
+    &self.references
+  }
+
   pub fn values(&self) -> &Vec<String> {
     &self.values
   }
+
+  pub fn file(&self) -> &String {
+    &self.file
+  }
+
+  pub fn metadata(&self) -> Metadata {
+    Metadata::new(self.cves.clone(), self.tags.clone(), self.severity, self.references.clone())
+  }
 }
 
 #[cfg(test)]
@@ -57,6 +238,55 @@ mod tests {
     assert_eq!(dict.data()[0].id(), "1");
     assert_eq!(dict.data()[0].description(), "test description");
     assert_eq!(dict.data()[0].values().len(), 2);
+    assert!(dict.signatures().is_empty());
+  }
+
+  #[test]
+  fn test_metadata_deserialization() {
+    let data = r#"
+        data:
+          - id: shellShockCheck
+            description: ShellShock
+            cves: [CVE-2014-6271]
+            tags: [rce, bash]
+            severity: critical
+            references: ["https://nvd.nist.gov/vuln/detail/CVE-2014-6271"]
+            values: ["() { 0; }; echo"]
+        "#;
+
+    let dict: FuzzyDictionary = serde_yaml::from_str(data).unwrap();
+    let metadata = dict.data()[0].metadata();
+    assert_eq!(metadata.cves(), &vec!["CVE-2014-6271".to_string()]);
+    assert_eq!(metadata.tags(), &vec!["rce".to_string(), "bash".to_string()]);
+    assert_eq!(metadata.severity(), Some(Severity::Critical));
+    assert_eq!(metadata.references(), &vec!["https://nvd.nist.gov/vuln/detail/CVE-2014-6271".to_string()]);
+  }
+
+  #[test]
+  fn test_merge_metadata() {
+    let mut metadata = Metadata::new(vec!["CVE-1".into()], vec!["sqli".into()], Some(Severity::Medium), vec![]);
+    metadata.merge(&Metadata::new(vec!["CVE-1".into(), "CVE-2".into()], vec!["rce".into()], Some(Severity::High), vec!["https://a".into()]));
+    metadata.merge(&Metadata::new(vec![], vec!["sqli".into()], Some(Severity::Low), vec![]));
+
+    assert_eq!(metadata.cves(), &vec!["CVE-1".to_string(), "CVE-2".to_string()]);
+    assert_eq!(metadata.tags(), &vec!["sqli".to_string(), "rce".to_string()]);
+    assert_eq!(metadata.severity(), Some(Severity::High));
+    assert_eq!(metadata.references(), &vec!["https://a".to_string()]);
+  }
+
+  #[test]
+  fn test_signatures_deserialization() {
+    let data = r#"
+        signatures:
+          - id: mysqlError
+            description: MySQL syntax error
+            patterns: ["You have an error in your SQL syntax", "mysql_fetch_\\w+"]
+        "#;
+
+    let dict: FuzzyDictionary = serde_yaml::from_str(data).unwrap();
+    assert!(dict.data().is_empty());
+    assert_eq!(dict.signatures()[0].id(), "mysqlError");
+    assert_eq!(dict.signatures()[0].patterns()[1], "mysql_fetch_\\w+");
   }
 
   #[test]
@@ -64,11 +294,56 @@ mod tests {
     let fuzzy_data = FuzzyData {
       id: "1".to_string(),
       description: "test description".to_string(),
+      cves: vec!["CVE-2014-6271".to_string()],
+      tags: vec!["rce".to_string()],
+      severity: Some(Severity::Critical),
+      references: vec!["https://nvd.nist.gov/vuln/detail/CVE-2014-6271".to_string()],
       values: vec!["value1".to_string(), "value2".to_string()],
+      file: "cves".to_string(),
     };
 
     assert_eq!(fuzzy_data.id(), "1");
     assert_eq!(fuzzy_data.description(), "test description");
+    assert_eq!(fuzzy_data.cves(), &vec!["CVE-2014-6271".to_string()]);
+    assert_eq!(fuzzy_data.tags(), &vec!["rce".to_string()]);
+    assert_eq!(fuzzy_data.severity(), Some(Severity::Critical));
+    assert_eq!(fuzzy_data.references().len(), 1);
     assert_eq!(fuzzy_data.values().len(), 2);
+    assert_eq!(fuzzy_data.file(), "cves");
+  }
+
+  #[test]
+  fn test_load_from_dir() {
+    let dict = FuzzyDictionary::load_from_dir("./dictionary").unwrap();
+
+    assert!(dict.data().iter().any(|data| data.id() == "sqlInjectionTest"));
+    assert!(dict.data().iter().any(|data| data.id() == "shellShockCheck" && data.file() == "cves"));
+    assert!(dict.signatures().iter().any(|signature| signature.id() == "ldapError"));
+    assert!(dict.signatures().iter().any(|signature| signature.id() == "unixPasswdFile"));
+  }
+
+  #[test]
+  fn test_load_wordlists_from_dir() {
+    let dir = std::env::temp_dir().join(format!("fuzzy-dictionary-wordlists-{}", std::process::id()));
+    std::fs::create_dir_all(dir.join("Usernames")).unwrap();
+    std::fs::write(dir.join("custom.yml"), "data:\n  - id: custom\n    description: custom\n    values: [\"a\"]\n").unwrap();
+    std::fs::write(dir.join("Usernames/top-usernames.txt"), "# most common\nroot\nadmin\n").unwrap();
+    std::fs::write(dir.join("Usernames/latin1.txt"), [0x6a, 0xe9, 0x72, 0xf4, 0x6d, 0x65]).unwrap();
+
+    let dict = FuzzyDictionary::load_from_dir(&dir).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let ids: Vec<&String> = dict.data().iter().map(FuzzyData::id).collect();
+    assert_eq!(ids, vec!["Usernames/latin1", "Usernames/top-usernames", "custom"]);
+    assert_eq!(dict.data()[0].values(), &vec!["j\u{fffd}r\u{fffd}me".to_string()]);
+    assert_eq!(dict.data()[1].values(), &vec!["root".to_string(), "admin".to_string()]);
+    assert_eq!(dict.data()[1].tags(), &vec!["usernames".to_string()]);
+  }
+
+  #[test]
+  fn test_load_from_missing_dir() {
+    let result = FuzzyDictionary::load_from_dir("./missing-dictionary");
+
+    assert!(matches!(result, Err(FuzzyError::Io { path, .. }) if path == "./missing-dictionary"));
   }
 }
@@ -1,11 +1,26 @@
-use serde::Deserialize;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
 use url::{ParseError, Url};
-
-#[derive(Clone, Debug, Deserialize)]
+use crate::domain::placeholder::{contains_placeholder, DEFAULT_PLACEHOLDER};
+use crate::domain::settings::Settings;
+use crate::domain::transform::Transform;
+
+/// Everything but the unreserved characters of RFC 3986.
+pub(crate) const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+  .remove(b'-')
+  .remove(b'.')
+  .remove(b'_')
+  .remove(b'~');
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct FuzzyInput {
   host: String,
   base_path: String,
   paths: Vec<Path>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  request_id_header: Option<String>,
+  #[serde(default, skip_serializing_if = "Settings::is_empty")]
+  settings: Settings,
 }
 
 impl FuzzyInput {
@@ -15,9 +30,21 @@ impl FuzzyInput {
       host,
       base_path,
       paths,
+      request_id_header: None,
+      settings: Settings::default(),
     }
   }
 
+  pub fn with_host(mut self, host: String) -> Self {
+    self.host = host;
+    self
+  }
+
+  pub fn with_request_id_header(mut self, request_id_header: Option<String>) -> Self {
+    self.request_id_header = request_id_header;
+    self
+  }
+
   pub fn host(&self) -> &String {
     &self.host
   }
@@ -29,9 +56,18 @@ impl FuzzyInput {
   pub fn paths(&self) -> &Vec<Path> {
     &self.paths
   }
+
+  /// Header every request carries its request id in, e.g. `X-Fuzzy-Request-Id`, none when not set.
+  pub fn request_id_header(&self) -> &Option<String> {
+    &self.request_id_header
+  }
+
+  pub fn settings(&self) -> &Settings {
+    &self.settings
+  }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Path {
   endpoint: String,
   method: HttpMethod,
@@ -41,10 +77,26 @@ pub struct Path {
   query_parameters: Vec<QueryParameter>,
   headers: Vec<HeaderParameter>,
   body: String,
+  #[serde(default)]
+  path_encoding: PathEncoding,
+  #[serde(default)]
+  body_match: BodyMatch,
+  #[serde(default)]
+  reflection: ReflectionMode,
+  #[serde(default)]
+  attack: AttackStrategy,
+  #[serde(default)]
+  fuzz_points: Vec<FuzzPoint>,
+  /// Dictionary selectors for every placeholder of the path, replacing the ones of the run settings.
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  include: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  exclude: Vec<String>,
 }
 
 impl Path {
 
+  #[allow(clippy::too_many_arguments)]
   pub fn new(endpoint: String, method: HttpMethod, expected_status: u16, expected_headers: Vec<ExpectedHeader>, expected_body: String, query_parameters: Vec<QueryParameter>, headers: Vec<HeaderParameter>, body: String) -> Self {
     Self {
       endpoint,
@@ -55,8 +107,43 @@ impl Path {
       query_parameters,
       headers,
       body,
+      path_encoding: PathEncoding::default(),
+      body_match: BodyMatch::default(),
+      reflection: ReflectionMode::default(),
+      attack: AttackStrategy::default(),
+      fuzz_points: Vec::new(),
+      include: Vec::new(),
+      exclude: Vec::new(),
     }
   }
+
+  pub fn with_selection(mut self, include: Vec<String>, exclude: Vec<String>) -> Self {
+    self.include = include;
+    self.exclude = exclude;
+    self
+  }
+
+  pub fn with_attack(mut self, attack: AttackStrategy, fuzz_points: Vec<FuzzPoint>) -> Self {
+    self.attack = attack;
+    self.fuzz_points = fuzz_points;
+    self
+  }
+
+  pub fn with_path_encoding(mut self, path_encoding: PathEncoding) -> Self {
+    self.path_encoding = path_encoding;
+    self
+  }
+
+  pub fn with_body_match(mut self, body_match: BodyMatch) -> Self {
+    self.body_match = body_match;
+    self
+  }
+
+  pub fn with_reflection(mut self, reflection: ReflectionMode) -> Self {
+    self.reflection = reflection;
+    self
+  }
+
   pub fn endpoint(&self) -> &String {
     &self.endpoint
   }
@@ -89,19 +176,58 @@ impl Path {
     &self.query_parameters
   }
 
-  pub fn to_url(&self, base_host: &String, base_path: &String) -> Result<Url, ParseError> {
-    let combined_path = format!(
-      "{}/{}/{}",
-      base_host.trim_end_matches('/'),
-      base_path.trim_start_matches('/').trim_end_matches('/'),
-      &self.endpoint.trim_start_matches('/')
-    );
+  pub fn body_match(&self) -> &BodyMatch {
+    &self.body_match
+  }
+
+  pub fn attack(&self) -> &AttackStrategy {
+    &self.attack
+  }
+
+  pub fn fuzz_points(&self) -> &Vec<FuzzPoint> {
+    &self.fuzz_points
+  }
+
+  pub fn fuzz_point(&self, name: &str) -> Option<&FuzzPoint> {
+    self.fuzz_points.iter().find(|point| point.name() == name)
+  }
+
+  pub fn include(&self) -> &Vec<String> {
+    &self.include
+  }
+
+  pub fn exclude(&self) -> &Vec<String> {
+    &self.exclude
+  }
+
+  pub fn reflection(&self) -> &ReflectionMode {
+    &self.reflection
+  }
+
+  pub fn path_encoding(&self) -> &PathEncoding {
+    &self.path_encoding
+  }
+
+  pub fn content_type(&self) -> Option<&str> {
+    self.headers
+      .iter()
+      .find(|header| header.name().eq_ignore_ascii_case("content-type"))
+      .and_then(|header| header.value().as_deref())
+  }
+
+  pub fn to_url(&self, base_host: &str, base_path: &str) -> Result<Url, ParseError> {
+    let combined_path = [base_host.trim_end_matches('/'), base_path.trim_matches('/'), self.endpoint.trim_start_matches('/')]
+      .iter()
+      .filter(|part| !part.is_empty())
+      .copied()
+      .collect::<Vec<_>>()
+      .join("/");
 
     let params_to_fuzz: Vec<(&str, &str)> = self.query_parameters()
       .iter()
       .filter_map(|param| {
         if param.fuzz() {
-          Some((param.name().as_str(), "{fuzz}"))
+          Some((param.name().as_str(), param.template()))
         } else {
           param.value().as_ref().map(|value| (param.name().as_str(), value.as_str()))
         }
@@ -112,13 +238,20 @@ impl Path {
   }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct ExpectedHeader {
   name: String,
   value: String,
+  #[serde(default, rename = "match")]
+  match_mode: HeaderMatch,
 }
 
 impl ExpectedHeader {
+
+  pub fn new(name: String, value: String, match_mode: HeaderMatch) -> Self {
+    Self { name, value, match_mode }
+  }
+
   pub fn name(&self) -> &String {
     &self.name
   }
@@ -126,9 +259,24 @@ impl ExpectedHeader {
   pub fn value(&self) -> &String {
     &self.value
   }
+
+  pub fn match_mode(&self) -> &HeaderMatch {
+    &self.match_mode
+  }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// How the value of an expected header is compared with the response header.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HeaderMatch {
+  #[default]
+  Exact,
+  CaseInsensitive,
+  Prefix,
+  Regex,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct HeaderParameter {
   name: String,
   value: Option<String>,
@@ -136,6 +284,20 @@ pub struct HeaderParameter {
 }
 
 impl HeaderParameter {
+
+  pub fn new(name: String, value: Option<String>, fuzz: bool) -> Self {
+    Self { name, value, fuzz }
+  }
+
+  /// The value to substitute placeholders into, a fuzzed header without a placeholder is replaced entirely.
+  pub fn template(&self) -> &str {
+    match &self.value {
+      Some(value) if !self.fuzz || contains_placeholder(value) => value,
+      Some(_) | None if self.fuzz => DEFAULT_PLACEHOLDER,
+      _ => "",
+    }
+  }
+
   pub fn name(&self) -> &String {
     &self.name
   }
@@ -149,7 +311,8 @@ impl HeaderParameter {
   }
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub enum HttpMethod {
   GET,
   POST,
@@ -160,7 +323,130 @@ pub enum HttpMethod {
   OPTIONS,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// How `expected_body` is compared with the response body, an empty `expected_body` is not checked.
+///
+/// `wildcard` compares literally with `*` matching any text, `regex` searches the body for the pattern and
+/// `json` compares the parsed documents ignoring key order, where an expected value of `"*"` matches anything.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BodyMatch {
+  #[default]
+  Wildcard,
+  Regex,
+  Json,
+}
+
+/// How payloads are combined when a request has more than one named placeholder.
+///
+/// `sniper` fuzzes one placeholder at a time leaving the others at their default, `battering_ram` puts the same value
+/// in every placeholder, `pitchfork` walks the payload lists of all placeholders in step and `cluster_bomb` tries
+/// every combination.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AttackStrategy {
+  #[default]
+  Sniper,
+  BatteringRam,
+  Pitchfork,
+  ClusterBomb,
+}
+
+/// Settings of a named placeholder, e.g. `fuzz1` for `{fuzz1}`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct FuzzPoint {
+  name: String,
+  #[serde(default)]
+  include: Vec<String>,
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  exclude: Vec<String>,
+  #[serde(default)]
+  default: String,
+  #[serde(default, skip_serializing_if = "Vec::is_empty", with = "serde_yaml::with::singleton_map_recursive")]
+  transforms: Vec<Vec<Transform>>,
+}
+
+impl FuzzPoint {
+
+  pub fn new(name: String, include: Vec<String>, default: String) -> Self {
+    Self { name, include, exclude: Vec::new(), default, transforms: Vec::new() }
+  }
+
+  pub fn with_exclude(mut self, exclude: Vec<String>) -> Self {
+    self.exclude = exclude;
+    self
+  }
+
+  pub fn with_transforms(mut self, transforms: Vec<Vec<Transform>>) -> Self {
+    self.transforms = transforms;
+    self
+  }
+
+  pub fn name(&self) -> &String {
+    &self.name
+  }
+
+  /// Dictionary selectors this placeholder is fuzzed with, replacing the ones of its path and the run when set,
+  /// e.g. `numericStrings` or `tag:sqli`.
+  pub fn include(&self) -> &Vec<String> {
+    &self.include
+  }
+
+  /// Dictionary selectors this placeholder is not fuzzed with, on top of the ones of its path and the run.
+  pub fn exclude(&self) -> &Vec<String> {
+    &self.exclude
+  }
+
+  /// The value used while other placeholders are being fuzzed.
+  pub fn default(&self) -> &String {
+    &self.default
+  }
+
+  /// Transform chains applied to every dictionary value, each chain sending its own variant of the value. Without
+  /// any the values are sent as they are, an empty chain keeps the original value next to the variants.
+  pub fn transforms(&self) -> &Vec<Vec<Transform>> {
+    &self.transforms
+  }
+}
+
+/// Whether a response is flagged depending on the fuzzed value showing up in its body or headers.
+///
+/// `reflected` flags responses echoing the value, `not_reflected` flags the ones that do not.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReflectionMode {
+  #[default]
+  Off,
+  Reflected,
+  NotReflected,
+}
+
+/// How a value is encoded before it replaces a `{fuzz}` placeholder in the endpoint.
+///
+/// `raw` values are still parsed as a url before sending, so dot segments such as `../` are normalised away,
+/// `double` encodes the value twice to get traversal sequences past filters that decode only once.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PathEncoding {
+  Raw,
+  #[default]
+  Percent,
+  Double,
+}
+
+impl PathEncoding {
+  pub fn encode(&self, value: &str) -> String {
+    match self {
+      PathEncoding::Raw => value.to_string(),
+      PathEncoding::Percent => utf8_percent_encode(value, PATH_SEGMENT).to_string(),
+      PathEncoding::Double => {
+        let encoded = utf8_percent_encode(value, PATH_SEGMENT).to_string();
+        utf8_percent_encode(&encoded, PATH_SEGMENT).to_string()
+      }
+    }
+  }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct QueryParameter {
   name: String,
   fuzz: bool,
@@ -173,6 +459,14 @@ impl QueryParameter {
   pub fn new(name: String, fuzz: bool, value: Option<String>) -> Self {
     Self { name, fuzz, value }
   }
+
+  /// The value of a fuzzed parameter, which is its own value when it names placeholders, e.g. `{fuzz1}{fuzz2}`.
+  pub fn template(&self) -> &str {
+    match &self.value {
+      Some(value) if contains_placeholder(value) => value,
+      _ => DEFAULT_PLACEHOLDER,
+    }
+  }
   
   pub fn name(&self) -> &String {
     &self.name
@@ -203,6 +497,36 @@ mod tests {
     let fuzzy_input: FuzzyInput = serde_yaml::from_str(data).unwrap();
     assert_eq!(fuzzy_input.host(), "http://example.com");
     assert_eq!(fuzzy_input.base_path(), "/api/v1");
+    assert_eq!(fuzzy_input.request_id_header(), &None);
+  }
+
+  #[test]
+  fn test_fuzzy_input_request_id_header_deserialization() {
+    let data = r#"
+        host: "http://example.com"
+        base_path: "/api/v1"
+        request_id_header: X-Fuzzy-Request-Id
+        paths: []
+        "#;
+
+    let fuzzy_input: FuzzyInput = serde_yaml::from_str(data).unwrap();
+    assert_eq!(fuzzy_input.request_id_header(), &Some("X-Fuzzy-Request-Id".to_string()));
+  }
+
+  #[test]
+  fn test_fuzzy_input_settings_deserialization() {
+    let data = r#"
+        host: "http://example.com"
+        base_path: "/api/v1"
+        settings:
+          concurrency: 2
+          output: results.jsonl
+        paths: []
+        "#;
+
+    let fuzzy_input: FuzzyInput = serde_yaml::from_str(data).unwrap();
+    assert_eq!(fuzzy_input.settings().concurrency(), 2);
+    assert_eq!(fuzzy_input.settings().output(), "results.jsonl");
   }
 
   #[test]
@@ -233,6 +557,19 @@ mod tests {
     let expected_header: ExpectedHeader = serde_yaml::from_str(data).unwrap();
     assert_eq!(expected_header.name(), "Content-Type");
     assert_eq!(expected_header.value(), "application/json");
+    assert_eq!(expected_header.match_mode(), &HeaderMatch::Exact);
+  }
+
+  #[test]
+  fn test_expected_header_match_deserialization() {
+    let data = r#"
+        name: "Content-Type"
+        value: "application/json"
+        match: case_insensitive
+        "#;
+
+    let expected_header: ExpectedHeader = serde_yaml::from_str(data).unwrap();
+    assert_eq!(expected_header.match_mode(), &HeaderMatch::CaseInsensitive);
   }
 
   #[test]
@@ -257,7 +594,7 @@ mod tests {
 
     let query_param: QueryParameter = serde_yaml::from_str(data).unwrap();
     assert_eq!(query_param.name(), "test_param");
-    assert_eq!(query_param.fuzz(), true);
+    assert!(query_param.fuzz());
   }
 
   #[test]
@@ -282,6 +619,13 @@ mod tests {
       ],
       headers: vec![],
       body: "".to_string(),
+      path_encoding: PathEncoding::Percent,
+      body_match: BodyMatch::Wildcard,
+      reflection: ReflectionMode::Off,
+      attack: AttackStrategy::Sniper,
+      fuzz_points: vec![],
+      include: vec![],
+      exclude: vec![],
     };
 
     let base_host = &"http://example.com".to_string();
@@ -289,4 +633,165 @@ mod tests {
     let url = path.to_url(base_host, base_path).unwrap();
     assert_eq!(url.as_str(), "http://example.com/api/v1/test_endpoint?test=%7Bfuzz%7D&key=value");
   }
+
+  #[test]
+  fn test_path_encoding_deserialization() {
+    let data = r#"
+        endpoint: "/users/{fuzz}/orders"
+        method: GET
+        expected_status: 200
+        expected_headers: []
+        expected_body: ""
+        query_parameters: []
+        headers: []
+        body: ""
+        path_encoding: double
+        "#;
+
+    let path: Path = serde_yaml::from_str(data).unwrap();
+    assert_eq!(path.path_encoding(), &PathEncoding::Double);
+    assert_eq!(path.body_match(), &BodyMatch::Wildcard);
+  }
+
+  #[test]
+  fn test_body_match_deserialization() {
+    let data = r#"
+        endpoint: "/test"
+        method: GET
+        expected_status: 200
+        expected_headers: []
+        expected_body: '{ "hello": "*" }'
+        body_match: json
+        query_parameters: []
+        headers: []
+        body: ""
+        "#;
+
+    let path: Path = serde_yaml::from_str(data).unwrap();
+    assert_eq!(path.body_match(), &BodyMatch::Json);
+    assert_eq!(path.reflection(), &ReflectionMode::Off);
+  }
+
+  #[test]
+  fn test_reflection_deserialization() {
+    let data = r#"
+        endpoint: "/search"
+        method: GET
+        expected_status: 200
+        expected_headers: []
+        expected_body: ""
+        reflection: not_reflected
+        query_parameters: []
+        headers: []
+        body: ""
+        "#;
+
+    let path: Path = serde_yaml::from_str(data).unwrap();
+    assert_eq!(path.reflection(), &ReflectionMode::NotReflected);
+  }
+
+  #[test]
+  fn test_path_encoding_defaults_to_percent() {
+    let path = Path::new("/users/{fuzz}".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], vec![], "".into());
+    assert_eq!(path.path_encoding(), &PathEncoding::Percent);
+  }
+
+  #[test]
+  fn test_path_encoding_encode() {
+    assert_eq!(PathEncoding::Raw.encode("../etc/passwd"), "../etc/passwd");
+    assert_eq!(PathEncoding::Percent.encode("../etc/passwd"), "..%2Fetc%2Fpasswd");
+    assert_eq!(PathEncoding::Double.encode("../etc/passwd"), "..%252Fetc%252Fpasswd");
+    assert_eq!(PathEncoding::Percent.encode("a b?#"), "a%20b%3F%23");
+  }
+
+  #[test]
+  fn test_path_to_url_with_fuzzed_segment() {
+    let path = Path::new("/users/{fuzz}/orders".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], vec![], "".into());
+
+    let url = path.to_url("http://example.com", "/api/v1").unwrap();
+    assert_eq!(url.as_str(), "http://example.com/api/v1/users/%7Bfuzz%7D/orders?");
+  }
+
+  #[test]
+  fn test_path_to_url_without_base_path() {
+    let path = Path::new("/users".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], vec![], "".into());
+
+    assert_eq!(path.to_url("http://example.com/", "").unwrap().as_str(), "http://example.com/users?");
+    assert_eq!(path.to_url("http://example.com", "/").unwrap().as_str(), "http://example.com/users?");
+  }
+
+  #[test]
+  fn test_attack_deserialization() {
+    let data = r#"
+        endpoint: "/users/{fuzz_id}"
+        method: GET
+        expected_status: 200
+        expected_headers: []
+        expected_body: ""
+        attack: cluster_bomb
+        fuzz_points:
+          - name: fuzz_id
+            include: [numericStrings]
+            default: "1"
+          - name: fuzz_type
+            exclude: ["file:strings"]
+            transforms:
+              - []
+              - [url]
+              - [{ prefix: "'" }, base64]
+        query_parameters:
+          - name: type
+            fuzz: true
+            value: "{fuzz_type}"
+        headers: []
+        body: ""
+        include: ["tag:sqli"]
+        "#;
+
+    let path: Path = serde_yaml::from_str(data).unwrap();
+    assert_eq!(path.attack(), &AttackStrategy::ClusterBomb);
+    assert_eq!(path.include(), &vec!["tag:sqli".to_string()]);
+    assert!(path.exclude().is_empty());
+    assert_eq!(path.fuzz_point("fuzz_type").unwrap().exclude(), &vec!["file:strings".to_string()]);
+    assert_eq!(path.fuzz_points().len(), 2);
+    assert_eq!(path.fuzz_point("fuzz_id").unwrap().include(), &vec!["numericStrings".to_string()]);
+    assert_eq!(path.fuzz_point("fuzz_id").unwrap().default(), "1");
+    assert_eq!(path.fuzz_point("fuzz_type").unwrap().default(), "");
+    assert_eq!(path.fuzz_point("fuzz_type").unwrap().transforms(), &vec![
+      vec![],
+      vec![Transform::Url],
+      vec![Transform::Prefix("'".into()), Transform::Base64],
+    ]);
+    assert!(path.fuzz_point("fuzz_id").unwrap().transforms().is_empty());
+    assert!(path.fuzz_point("fuzz").is_none());
+  }
+
+  #[test]
+  fn test_path_to_url_with_named_placeholders() {
+    let path = Path::new(
+      "/test".into(),
+      HttpMethod::GET,
+      200,
+      vec![],
+      "".into(),
+      vec![
+        QueryParameter::new("a".into(), true, Some("{fuzz1}{fuzz2}".into())),
+        QueryParameter::new("b".into(), true, Some("plain".into())),
+      ],
+      vec![],
+      "".into()
+    );
+
+    let url = path.to_url("http://example.com", "/api").unwrap();
+    assert_eq!(url.as_str(), "http://example.com/api/test?a=%7Bfuzz1%7D%7Bfuzz2%7D&b=%7Bfuzz%7D");
+  }
+
+  #[test]
+  fn test_header_parameter_template() {
+    assert_eq!(HeaderParameter::new("A".into(), None, true).template(), "{fuzz}");
+    assert_eq!(HeaderParameter::new("A".into(), Some("x".into()), true).template(), "{fuzz}");
+    assert_eq!(HeaderParameter::new("A".into(), Some("Bearer {fuzz2}".into()), true).template(), "Bearer {fuzz2}");
+    assert_eq!(HeaderParameter::new("A".into(), Some("x".into()), false).template(), "x");
+    assert_eq!(HeaderParameter::new("A".into(), None, false).template(), "");
+  }
 }
@@ -1,2 +1,7 @@
 pub mod dictionary;
-pub mod input;
\ No newline at end of file
+pub mod input;
+pub mod placeholder;
+pub mod selector;
+pub mod settings;
+pub mod transform;
+pub mod wordlist;
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+use regex::{Captures, Regex};
+
+/// The placeholder used where a fuzzed parameter does not name one itself.
+pub const DEFAULT_PLACEHOLDER: &str = "{fuzz}";
+
+/// Matches `{fuzz}`, `{fuzz1}`, `{fuzz_user}` etc. both raw and as percent encoded by the url parser.
+fn placeholder_regex() -> &'static Regex {
+  static PLACEHOLDER: OnceLock<Regex> = OnceLock::new();
+  PLACEHOLDER.get_or_init(|| {
+    Regex::new(r"\{(fuzz[A-Za-z0-9_]*)\}|%7B(fuzz[A-Za-z0-9_]*)%7D").expect("placeholder regex is valid")
+  })
+}
+
+fn placeholder_name<'a>(captures: &Captures<'a>) -> &'a str {
+  captures.get(1)
+    .or_else(|| captures.get(2))
+    .map(|name| name.as_str())
+    .unwrap_or_default()
+}
+
+pub fn contains_placeholder(text: &str) -> bool {
+  placeholder_regex().is_match(text)
+}
+
+/// Names of the placeholders in the text, in order of first appearance.
+pub fn placeholder_names(text: &str) -> Vec<String> {
+  let mut names: Vec<String> = Vec::new();
+
+  for captures in placeholder_regex().captures_iter(text) {
+    let name = placeholder_name(&captures);
+    if !names.iter().any(|existing| existing == name) {
+      names.push(name.to_string());
+    }
+  }
+
+  names
+}
+
+/// Replaces every placeholder with a value in a single pass, so a payload containing a placeholder is left as is.
+/// Placeholders without a value are kept.
+pub fn replace_placeholders<F: Fn(&str) -> String>(text: &str, values: &HashMap<String, String>, encode: F) -> String {
+  replace_placeholders_at(text, values, |_, value| encode(value))
+}
+
+/// Like `replace_placeholders`, with the byte offset of each placeholder in the text passed along with its value.
+pub fn replace_placeholders_at<F: Fn(usize, &str) -> String>(text: &str, values: &HashMap<String, String>, encode: F) -> String {
+  placeholder_regex()
+    .replace_all(text, |captures: &Captures| {
+      match values.get(placeholder_name(captures)) {
+        Some(value) => encode(captures.get(0).map_or(0, |placeholder| placeholder.start()), value),
+        None => captures[0].to_string(),
+      }
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_placeholder_names() {
+    let names = placeholder_names("/users/%7Bfuzz_id%7D?q={fuzz1}{fuzz2}&r={fuzz1}&s={fuzzy-not}&t={fuzz}");
+    assert_eq!(names, vec!["fuzz_id", "fuzz1", "fuzz2", "fuzz"]);
+  }
+
+  #[test]
+  fn test_contains_placeholder() {
+    assert!(contains_placeholder("Bearer {fuzz}"));
+    assert!(contains_placeholder("a%7Bfuzz2%7D"));
+    assert!(!contains_placeholder("{user}"));
+  }
+
+  #[test]
+  fn test_replace_placeholders() {
+    let values = HashMap::from([
+      ("fuzz1".to_string(), "{fuzz2}".to_string()),
+      ("fuzz2".to_string(), "b".to_string()),
+    ]);
+
+    let replaced = replace_placeholders("{fuzz1}-%7Bfuzz2%7D-{fuzz3}", &values, |value| value.to_uppercase());
+    assert_eq!(replaced, "{FUZZ2}-B-{fuzz3}");
+  }
+}
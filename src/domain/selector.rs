@@ -0,0 +1,136 @@
+use crate::domain::dictionary::FuzzyData;
+use crate::domain::wordlist::dictionary_name;
+
+/// Picks dictionaries by `id:`, `tag:`, `cve:` or `file:` (also `category:`), e.g. `tag:sqli` or `file:strings`.
+/// A selector without a prefix is an id, so `numericStrings` is the same as `id:numericStrings`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Selector {
+  Id(String),
+  Tag(String),
+  Cve(String),
+  /// The path of the dictionary file in the dictionary directory, with or without its extension, or of a directory
+  /// to select every file under it.
+  File(String),
+}
+
+impl Selector {
+  pub fn parse(selector: &str) -> Self {
+    match selector.split_once(':') {
+      Some(("id", id)) => Selector::Id(id.to_string()),
+      Some(("tag", tag)) => Selector::Tag(tag.to_string()),
+      Some(("cve", cve)) => Selector::Cve(cve.to_string()),
+      Some(("file" | "category", file)) => Selector::File(dictionary_name(file.trim_end_matches('/')).to_string()),
+      _ => Selector::Id(selector.to_string()),
+    }
+  }
+
+  /// Tags, cves and files are compared ignoring case, ids are not.
+  pub fn matches(&self, data: &FuzzyData) -> bool {
+    match self {
+      Selector::Id(id) => data.id() == id,
+      Selector::Tag(tag) => data.tags().iter().any(|data_tag| data_tag.eq_ignore_ascii_case(tag)),
+      Selector::Cve(cve) => data.cves().iter().any(|data_cve| data_cve.eq_ignore_ascii_case(cve)),
+      Selector::File(file) => {
+        let data_file = data.file().to_lowercase();
+        let file = file.to_lowercase();
+        data_file == file || data_file.strip_prefix(&file).is_some_and(|rest| rest.starts_with('/'))
+      }
+    }
+  }
+}
+
+/// Which dictionaries a placeholder is fuzzed with, every one unless included, and then only the included ones that
+/// are not excluded.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Selection {
+  include: Vec<Selector>,
+  exclude: Vec<Selector>,
+}
+
+impl Selection {
+  pub fn new(include: &[String], exclude: &[String]) -> Self {
+    Self {
+      include: include.iter().map(|selector| Selector::parse(selector)).collect(),
+      exclude: exclude.iter().map(|selector| Selector::parse(selector)).collect(),
+    }
+  }
+
+  /// A selection for a narrower scope, e.g. a path of the run or a fuzz point of the path. Its include selectors
+  /// replace the broader ones when it has any, while exclude selectors of every scope apply.
+  pub fn narrow(&self, include: &[String], exclude: &[String]) -> Self {
+    let narrower = Selection::new(include, exclude);
+
+    Self {
+      include: if narrower.include.is_empty() { self.include.clone() } else { narrower.include },
+      exclude: self.exclude.iter().cloned().chain(narrower.exclude).collect(),
+    }
+  }
+
+  pub fn selects(&self, data: &FuzzyData) -> bool {
+    (self.include.is_empty() || self.include.iter().any(|selector| selector.matches(data)))
+      && !self.exclude.iter().any(|selector| selector.matches(data))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::domain::dictionary::FuzzyDictionary;
+  use super::*;
+
+  fn sample_data() -> Vec<FuzzyData> {
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: numericStrings
+          description: numbers
+          values: ["1"]
+        - id: sqlInjectionTest
+          description: sql
+          tags: [sqli]
+          values: ["'"]
+        - id: shellShockCheck
+          description: shellshock
+          cves: [CVE-2014-6271]
+          tags: [rce]
+          values: ["() { 0; };"]
+      "#).unwrap();
+
+    dict.data().iter().cloned().map(|data| {
+      let file = if data.id() == "numericStrings" { "strings" } else { "Fuzzing/injections" };
+      data.with_file(file.into())
+    }).collect()
+  }
+
+  fn selected(selection: &Selection) -> Vec<String> {
+    sample_data().into_iter().filter(|data| selection.selects(data)).map(|data| data.id().clone()).collect()
+  }
+
+  #[test]
+  fn test_parse() {
+    assert_eq!(Selector::parse("numericStrings"), Selector::Id("numericStrings".into()));
+    assert_eq!(Selector::parse("id:numericStrings"), Selector::Id("numericStrings".into()));
+    assert_eq!(Selector::parse("tag:sqli"), Selector::Tag("sqli".into()));
+    assert_eq!(Selector::parse("cve:CVE-2014-6271"), Selector::Cve("CVE-2014-6271".into()));
+    assert_eq!(Selector::parse("file:strings.yml"), Selector::File("strings".into()));
+    assert_eq!(Selector::parse("category:strings"), Selector::File("strings".into()));
+  }
+
+  #[test]
+  fn test_selects() {
+    assert_eq!(selected(&Selection::default()).len(), 3);
+    assert_eq!(selected(&Selection::new(&["tag:SQLI".into(), "cve:cve-2014-6271".into()], &[])), vec!["sqlInjectionTest", "shellShockCheck"]);
+    assert_eq!(selected(&Selection::new(&[], &["file:strings".into()])), vec!["sqlInjectionTest", "shellShockCheck"]);
+    assert_eq!(selected(&Selection::new(&["file:fuzzing/injections.yml".into()], &["tag:rce".into()])), vec!["sqlInjectionTest"]);
+    assert_eq!(selected(&Selection::new(&["file:Fuzzing/".into()], &[])), vec!["sqlInjectionTest", "shellShockCheck"]);
+    assert!(selected(&Selection::new(&["file:Fuzz".into()], &[])).is_empty());
+  }
+
+  #[test]
+  fn test_narrow() {
+    let run = Selection::new(&["file:Fuzzing".into()], &["tag:rce".into()]);
+
+    assert_eq!(selected(&run.narrow(&[], &[])), vec!["sqlInjectionTest"]);
+    assert_eq!(selected(&run.narrow(&["numericStrings".into(), "sqlInjectionTest".into()], &[])), vec!["numericStrings", "sqlInjectionTest"]);
+    assert!(selected(&run.narrow(&["shellShockCheck".into()], &[])).is_empty());
+    assert!(selected(&run.narrow(&[], &["sqlInjectionTest".into()])).is_empty());
+  }
+}
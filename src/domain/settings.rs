@@ -0,0 +1,247 @@
+use serde::{Deserialize, Serialize};
+use crate::error::FuzzyError;
+use crate::fuzzer::output::OutputFormat;
+
+pub const DEFAULT_DICTIONARY_DIR: &str = "./dictionary";
+pub const DEFAULT_OUTPUT: &str = "output.txt";
+pub const DEFAULT_CONCURRENCY: usize = 10;
+pub const DEFAULT_CHANNEL_SIZE: usize = 32;
+pub const DEFAULT_METRICS_INTERVAL: u64 = 5;
+pub const DEFAULT_BASELINE_REQUESTS: usize = 3;
+pub const DEFAULT_ANOMALY_THRESHOLD: f64 = 1.0;
+pub const DEFAULT_DELAY_FACTOR: f64 = 5.0;
+pub const DEFAULT_RATE_LIMIT: f64 = 0.0;
+pub const DEFAULT_HOST_RATE_LIMIT: f64 = 0.0;
+pub const DEFAULT_BURST: usize = 1;
+pub const DEFAULT_JITTER_MS: u64 = 0;
+
+/// Run settings from the `settings` section of an input file, any of them can be overridden from the command line.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub dictionary_dir: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub output: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub format: Option<OutputFormat>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub concurrency: Option<usize>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub channel_size: Option<usize>,
+  /// Seconds between metrics reports.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub metrics_interval: Option<u64>,
+  /// Benign requests sent per path before fuzzing it, fuzzed responses are then scored against them instead of
+  /// being checked against `expected_status`, which 0 checks instead.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub baseline_requests: Option<usize>,
+  /// Score from which a response deviating from the baseline is reported.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub anomaly_threshold: Option<f64>,
+  /// Responses taking this many times the baseline median latency, or the latency of a request with the defaults
+  /// without a baseline, are flagged, 0 turns this off.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub delay_factor: Option<f64>,
+  /// Requests per second over every host, 0 is unlimited.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub rate_limit: Option<f64>,
+  /// Requests per second to each host, 0 is unlimited.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub host_rate_limit: Option<f64>,
+  /// Requests sent at once after a pause before the rate limits apply.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub burst: Option<usize>,
+  /// Longest random pause in milliseconds before each request.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub jitter_ms: Option<u64>,
+  /// Dictionary selectors to fuzz with, e.g. `sqlInjectionTest`, `tag:sqli`, `cve:CVE-2014-6271` or `file:strings`,
+  /// every dictionary when empty.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub include: Option<Vec<String>>,
+  /// Dictionary selectors not to fuzz with.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub exclude: Option<Vec<String>>,
+}
+
+impl Settings {
+  pub fn is_empty(&self) -> bool {
+    self == &Settings::default()
+  }
+
+  /// Values set in `overrides` take precedence over the ones set here.
+  pub fn override_with(&self, overrides: &Settings) -> Settings {
+    Settings {
+      dictionary_dir: overrides.dictionary_dir.clone().or_else(|| self.dictionary_dir.clone()),
+      output: overrides.output.clone().or_else(|| self.output.clone()),
+      format: overrides.format.or(self.format),
+      concurrency: overrides.concurrency.or(self.concurrency),
+      channel_size: overrides.channel_size.or(self.channel_size),
+      metrics_interval: overrides.metrics_interval.or(self.metrics_interval),
+      baseline_requests: overrides.baseline_requests.or(self.baseline_requests),
+      anomaly_threshold: overrides.anomaly_threshold.or(self.anomaly_threshold),
+      delay_factor: overrides.delay_factor.or(self.delay_factor),
+      rate_limit: overrides.rate_limit.or(self.rate_limit),
+      host_rate_limit: overrides.host_rate_limit.or(self.host_rate_limit),
+      burst: overrides.burst.or(self.burst),
+      jitter_ms: overrides.jitter_ms.or(self.jitter_ms),
+      include: overrides.include.clone().or_else(|| self.include.clone()),
+      exclude: overrides.exclude.clone().or_else(|| self.exclude.clone()),
+    }
+  }
+
+  /// Settings that a run cannot start with, as the field and what is wrong with it. Flags are checked when parsed,
+  /// while the input file settings are checked here.
+  pub fn problems(&self) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    if self.concurrency == Some(0) {
+      problems.push(("concurrency", "concurrency must be at least 1".to_string()));
+    }
+    if self.channel_size == Some(0) {
+      problems.push(("channel_size", "channel_size must be at least 1".to_string()));
+    }
+    if self.metrics_interval == Some(0) {
+      problems.push(("metrics_interval", "metrics_interval must be at least 1".to_string()));
+    }
+
+    problems
+  }
+
+  /// Fails on the first of the `problems`.
+  pub fn check(&self) -> Result<(), FuzzyError> {
+    match self.problems().into_iter().next() {
+      Some((_, message)) => Err(FuzzyError::InvalidSettings { message }),
+      None => Ok(()),
+    }
+  }
+
+  pub fn dictionary_dir(&self) -> &str {
+    self.dictionary_dir.as_deref().unwrap_or(DEFAULT_DICTIONARY_DIR)
+  }
+
+  pub fn output(&self) -> &str {
+    self.output.as_deref().unwrap_or(DEFAULT_OUTPUT)
+  }
+
+  /// The configured format, or the one implied by the output file extension.
+  pub fn format(&self) -> OutputFormat {
+    self.format.unwrap_or_else(|| OutputFormat::from_path(self.output()))
+  }
+
+  pub fn concurrency(&self) -> usize {
+    self.concurrency.unwrap_or(DEFAULT_CONCURRENCY)
+  }
+
+  pub fn channel_size(&self) -> usize {
+    self.channel_size.unwrap_or(DEFAULT_CHANNEL_SIZE)
+  }
+
+  pub fn metrics_interval(&self) -> u64 {
+    self.metrics_interval.unwrap_or(DEFAULT_METRICS_INTERVAL)
+  }
+
+  pub fn baseline_requests(&self) -> usize {
+    self.baseline_requests.unwrap_or(DEFAULT_BASELINE_REQUESTS)
+  }
+
+  pub fn anomaly_threshold(&self) -> f64 {
+    self.anomaly_threshold.unwrap_or(DEFAULT_ANOMALY_THRESHOLD)
+  }
+
+  pub fn delay_factor(&self) -> f64 {
+    self.delay_factor.unwrap_or(DEFAULT_DELAY_FACTOR)
+  }
+
+  pub fn rate_limit(&self) -> f64 {
+    self.rate_limit.unwrap_or(DEFAULT_RATE_LIMIT)
+  }
+
+  pub fn host_rate_limit(&self) -> f64 {
+    self.host_rate_limit.unwrap_or(DEFAULT_HOST_RATE_LIMIT)
+  }
+
+  pub fn burst(&self) -> usize {
+    self.burst.unwrap_or(DEFAULT_BURST)
+  }
+
+  pub fn jitter_ms(&self) -> u64 {
+    self.jitter_ms.unwrap_or(DEFAULT_JITTER_MS)
+  }
+
+  pub fn include(&self) -> &[String] {
+    self.include.as_deref().unwrap_or_default()
+  }
+
+  pub fn exclude(&self) -> &[String] {
+    self.exclude.as_deref().unwrap_or_default()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_defaults() {
+    let settings = Settings::default();
+
+    assert_eq!(settings.dictionary_dir(), "./dictionary");
+    assert_eq!(settings.output(), "output.txt");
+    assert_eq!(settings.format(), OutputFormat::Text);
+    assert_eq!(settings.concurrency(), 10);
+    assert_eq!(settings.channel_size(), 32);
+    assert_eq!(settings.metrics_interval(), 5);
+    assert_eq!(settings.baseline_requests(), 3);
+    assert_eq!(settings.anomaly_threshold(), 1.0);
+    assert_eq!(settings.delay_factor(), 5.0);
+    assert_eq!(settings.rate_limit(), 0.0);
+    assert_eq!(settings.host_rate_limit(), 0.0);
+    assert_eq!(settings.burst(), 1);
+    assert_eq!(settings.jitter_ms(), 0);
+    assert!(settings.include().is_empty());
+    assert!(settings.exclude().is_empty());
+  }
+
+  #[test]
+  fn test_deserialization() {
+    let data = r#"
+        output: results.jsonl
+        concurrency: 4
+        "#;
+
+    let settings: Settings = serde_yaml::from_str(data).unwrap();
+    assert_eq!(settings.output(), "results.jsonl");
+    assert_eq!(settings.format(), OutputFormat::Jsonl);
+    assert_eq!(settings.concurrency(), 4);
+  }
+
+  #[test]
+  fn test_override_with() {
+    let from_file = Settings {
+      output: Some("results.jsonl".into()),
+      concurrency: Some(4),
+      ..Default::default()
+    };
+    let from_cli = Settings {
+      concurrency: Some(20),
+      format: Some(OutputFormat::Text),
+      ..Default::default()
+    };
+
+    let settings = from_file.override_with(&from_cli);
+    assert_eq!(settings.output(), "results.jsonl");
+    assert_eq!(settings.format(), OutputFormat::Text);
+    assert_eq!(settings.concurrency(), 20);
+    assert_eq!(settings.channel_size(), 32);
+  }
+
+  #[test]
+  fn test_check() {
+    let settings: Settings = serde_yaml::from_str("concurrency: 0\nchannel_size: 0\nmetrics_interval: 0").unwrap();
+
+    assert_eq!(settings.problems().len(), 3);
+    assert_eq!(settings.problems()[2].1, "metrics_interval must be at least 1");
+    assert_eq!(settings.check().unwrap_err().to_string(), "invalid settings: concurrency must be at least 1");
+    assert!(Settings::default().check().is_ok());
+  }
+}
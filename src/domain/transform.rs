@@ -0,0 +1,142 @@
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use md5::Md5;
+use percent_encoding::utf8_percent_encode;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use sha2::Sha256;
+use crate::domain::input::PATH_SEGMENT;
+
+/// A step of a transform chain, applied to each dictionary value before it replaces a placeholder.
+///
+/// The value is still encoded for where the placeholder sits afterwards, e.g. percent encoded in the endpoint.
+/// Transforms taking a value are written as a single key map in YAML, e.g. `{ prefix: "'" }`, which fields need
+/// `serde_yaml::with::singleton_map_recursive` for.
+#[derive(Clone, Debug, Deserialize, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+  /// Percent encodes everything but the unreserved characters of RFC 3986.
+  Url,
+  /// Percent encodes twice, for filters that decode only once.
+  DoubleUrl,
+  Base64,
+  Hex,
+  /// Escapes `&`, `<`, `>`, `"` and `'` as HTML entities.
+  Html,
+  /// Writes every character as a decimal HTML entity, e.g. `&#39;`.
+  HtmlNumeric,
+  /// Writes every character as a `\uXXXX` escape of its UTF-16 code units.
+  Unicode,
+  Upper,
+  Lower,
+  SwapCase,
+  /// Alternates the case of letters, e.g. `sElEcT`.
+  AlternateCase,
+  Prefix(String),
+  Suffix(String),
+  /// Hex digest of the value.
+  Md5,
+  Sha1,
+  Sha256,
+}
+
+impl Transform {
+  pub fn apply(&self, value: &str) -> String {
+    match self {
+      Transform::Url => utf8_percent_encode(value, PATH_SEGMENT).to_string(),
+      Transform::DoubleUrl => {
+        let encoded = utf8_percent_encode(value, PATH_SEGMENT).to_string();
+        utf8_percent_encode(&encoded, PATH_SEGMENT).to_string()
+      }
+      Transform::Base64 => STANDARD.encode(value),
+      Transform::Hex => value.bytes().map(|byte| format!("{:02x}", byte)).collect(),
+      Transform::Html => value
+        .chars()
+        .map(|char| match char {
+          '&' => "&amp;".to_string(),
+          '<' => "&lt;".to_string(),
+          '>' => "&gt;".to_string(),
+          '"' => "&quot;".to_string(),
+          '\'' => "&#x27;".to_string(),
+          _ => char.to_string(),
+        })
+        .collect(),
+      Transform::HtmlNumeric => value.chars().map(|char| format!("&#{};", char as u32)).collect(),
+      Transform::Unicode => value.encode_utf16().map(|unit| format!("\\u{:04x}", unit)).collect(),
+      Transform::Upper => value.to_uppercase(),
+      Transform::Lower => value.to_lowercase(),
+      Transform::SwapCase => value
+        .chars()
+        .map(|char| if char.is_lowercase() { char.to_uppercase().to_string() } else { char.to_lowercase().to_string() })
+        .collect(),
+      Transform::AlternateCase => {
+        let mut upper = false;
+        value
+          .chars()
+          .map(|char| {
+            if !char.is_alphabetic() {
+              return char.to_string();
+            }
+            upper = !upper;
+            if upper { char.to_lowercase().to_string() } else { char.to_uppercase().to_string() }
+          })
+          .collect()
+      }
+      Transform::Prefix(prefix) => format!("{}{}", prefix, value),
+      Transform::Suffix(suffix) => format!("{}{}", value, suffix),
+      Transform::Md5 => format!("{:x}", Md5::digest(value)),
+      Transform::Sha1 => format!("{:x}", Sha1::digest(value)),
+      Transform::Sha256 => format!("{:x}", Sha256::digest(value)),
+    }
+  }
+}
+
+/// Applies every transform of the chain in order, an empty chain keeps the value as is.
+pub fn apply_chain(chain: &[Transform], value: &str) -> String {
+  chain.iter().fold(value.to_string(), |value, transform| transform.apply(&value))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_apply() {
+    let cases = vec![
+      (Transform::Url, "' OR 1=1--", "%27%20OR%201%3D1--"),
+      (Transform::DoubleUrl, "../", "..%252F"),
+      (Transform::Base64, "admin:admin", "YWRtaW46YWRtaW4="),
+      (Transform::Hex, "<a>", "3c613e"),
+      (Transform::Html, "<a href='x'>&", "&lt;a href=&#x27;x&#x27;&gt;&amp;"),
+      (Transform::HtmlNumeric, "<a", "&#60;&#97;"),
+      (Transform::Unicode, "<é😀", "\\u003c\\u00e9\\ud83d\\ude00"),
+      (Transform::Upper, "select", "SELECT"),
+      (Transform::Lower, "SELECT", "select"),
+      (Transform::SwapCase, "Select 1", "sELECT 1"),
+      (Transform::AlternateCase, "union select", "uNiOn SeLeCt"),
+      (Transform::Prefix("'".into()), "1", "'1"),
+      (Transform::Suffix("--".into()), "1", "1--"),
+      (Transform::Md5, "admin", "21232f297a57a5a743894a0e4a801fc3"),
+      (Transform::Sha1, "admin", "d033e22ae348aeb5660fc2140aec35850c4da997"),
+      (Transform::Sha256, "admin", "8c6976e5b5410415bde908bd4dee15dfb167a9c873fc4bb8a81f6f2ab448a918"),
+    ];
+
+    for (transform, value, expected) in cases {
+      assert_eq!(transform.apply(value), expected, "{:?}", transform);
+    }
+  }
+
+  #[test]
+  fn test_apply_chain() {
+    let chain = vec![Transform::Prefix("'".into()), Transform::Upper, Transform::Url];
+    assert_eq!(apply_chain(&chain, "or 1=1"), "%27OR%201%3D1");
+    assert_eq!(apply_chain(&[], "or 1=1"), "or 1=1");
+  }
+
+  #[test]
+  fn test_deserialization() {
+    let deserializer = serde_yaml::Deserializer::from_str(r#"[double_url, { prefix: "'" }, sha256]"#);
+    let chain: Vec<Transform> = serde_yaml::with::singleton_map_recursive::deserialize(deserializer).unwrap();
+    assert_eq!(chain, vec![Transform::DoubleUrl, Transform::Prefix("'".into()), Transform::Sha256]);
+  }
+}
@@ -0,0 +1,234 @@
+use std::fs::{read, read_dir};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use flate2::read::GzDecoder;
+use crate::domain::dictionary::FuzzyData;
+use crate::error::FuzzyError;
+
+/// Extensions of plain text wordlists, with one value per line.
+const WORDLIST_EXTENSIONS: [&str; 5] = ["txt", "lst", "list", "dic", "wordlist"];
+
+/// Lines of a wordlist starting with this are comments.
+const COMMENT: char = '#';
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DictionaryFormat {
+  /// The `data` and `signatures` sections of a yaml file.
+  Yaml,
+  /// A value per line, like the lists of SecLists.
+  Wordlist,
+}
+
+/// A file of a dictionary directory, found at any depth and possibly gzip compressed.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DictionaryFile {
+  path: PathBuf,
+  name: String,
+  format: DictionaryFormat,
+  compressed: bool,
+}
+
+impl DictionaryFile {
+  /// Returns `None` for files that are not dictionaries, e.g. `README.md` or images in a SecLists checkout.
+  fn new(path: PathBuf, relative: &str) -> Option<Self> {
+    let (uncompressed, compressed) = match relative.strip_suffix(".gz") {
+      Some(uncompressed) => (uncompressed, true),
+      None => (relative, false),
+    };
+    let (stem, extension) = uncompressed.rsplit_once('.')?;
+    let format = match extension.to_ascii_lowercase().as_str() {
+      "yml" | "yaml" => DictionaryFormat::Yaml,
+      extension if WORDLIST_EXTENSIONS.contains(&extension) => DictionaryFormat::Wordlist,
+      _ => return None,
+    };
+
+    Some(Self { path, name: stem.to_string(), format, compressed })
+  }
+
+  pub fn path(&self) -> &PathBuf {
+    &self.path
+  }
+
+  /// The path relative to the dictionary directory, without extensions, e.g. `Fuzzing/SQLi/Generic-SQLi`.
+  pub fn name(&self) -> &String {
+    &self.name
+  }
+
+  pub fn format(&self) -> DictionaryFormat {
+    self.format
+  }
+
+  /// The bytes of the file, decompressed when it ends with `.gz`.
+  pub fn read_bytes(&self) -> Result<Vec<u8>, FuzzyError> {
+    let file = self.path.display().to_string();
+    let bytes = read(&self.path).map_err(|err| FuzzyError::io(&file, err))?;
+
+    if !self.compressed {
+      return Ok(bytes);
+    }
+
+    let mut decompressed = Vec::new();
+    GzDecoder::new(bytes.as_slice()).read_to_end(&mut decompressed).map_err(|err| FuzzyError::io(&file, err))?;
+    Ok(decompressed)
+  }
+
+  /// The text of the file, decompressed when it ends with `.gz`, which has to be utf-8.
+  pub fn read(&self) -> Result<String, FuzzyError> {
+    String::from_utf8(self.read_bytes()?)
+      .map_err(|err| FuzzyError::io(&self.path.display().to_string(), io::Error::new(io::ErrorKind::InvalidData, err)))
+  }
+
+  /// A dictionary with every line that is neither empty nor a comment. Its id is the name of the file, and the
+  /// directories it sits in become its tags, e.g. `fuzzing` and `sqli` for `Fuzzing/SQLi/Generic-SQLi.txt`. Lines
+  /// are decoded one at a time, so bytes that are not utf-8, e.g. in latin-1 lists, only replace their own characters.
+  pub fn to_wordlist(&self, content: &[u8]) -> FuzzyData {
+    let values = content.split(|byte| *byte == b'\n')
+      .map(|line| String::from_utf8_lossy(line.strip_suffix(b"\r").unwrap_or(line)))
+      .filter(|line| !line.trim().is_empty() && !line.starts_with(COMMENT))
+      .map(|line| line.to_string())
+      .collect();
+    let tags = match self.name.rsplit_once('/') {
+      Some((directories, _)) => directories.split('/').map(|directory| directory.to_lowercase()).collect(),
+      None => Vec::new(),
+    };
+
+    FuzzyData::new(self.name.clone(), format!("wordlist {}", self.path.display()), values)
+      .with_tags(tags)
+      .with_file(self.name.clone())
+  }
+}
+
+/// Every dictionary file under the directory in path order, skipping hidden files and directories such as `.git`.
+pub fn dictionary_files<P: AsRef<Path>>(dir: P) -> Result<Vec<DictionaryFile>, FuzzyError> {
+  let mut files = Vec::new();
+  collect(dir.as_ref(), "", &mut files)?;
+  Ok(files)
+}
+
+fn collect(dir: &Path, prefix: &str, files: &mut Vec<DictionaryFile>) -> Result<(), FuzzyError> {
+  let mut entries: Vec<PathBuf> = read_dir(dir)
+    .map_err(|err| FuzzyError::io(&dir.display().to_string(), err))?
+    .flatten()
+    .map(|entry| entry.path())
+    .collect();
+  entries.sort();
+
+  for path in entries {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    if name.starts_with('.') {
+      continue;
+    }
+
+    let relative = format!("{}{}", prefix, name);
+    if path.is_dir() {
+      collect(&path, &format!("{}/", relative), files)?;
+    } else if let Some(file) = DictionaryFile::new(path.clone(), &relative) {
+      files.push(file);
+    } else {
+      log::debug!("skipping {}, it is neither yaml nor a wordlist", path.display());
+    }
+  }
+
+  Ok(())
+}
+
+/// The name a dictionary file is selected by, without its `.gz` and format extensions.
+pub fn dictionary_name(file: &str) -> &str {
+  let uncompressed = file.strip_suffix(".gz").unwrap_or(file);
+  match uncompressed.rsplit_once('.') {
+    Some((stem, extension)) if extension == "yml" || extension == "yaml" || WORDLIST_EXTENSIONS.contains(&extension) => stem,
+    _ => uncompressed,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::fs::{create_dir_all, remove_dir_all, write};
+  use std::io::Write;
+  use flate2::write::GzEncoder;
+  use flate2::Compression;
+  use super::*;
+
+  fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("fuzzy-{}-{}", name, std::process::id()));
+    create_dir_all(&dir).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_dictionary_files() {
+    let dir = temp_dir("wordlist-files");
+    create_dir_all(dir.join("Fuzzing/SQLi")).unwrap();
+    create_dir_all(dir.join(".git")).unwrap();
+    write(dir.join("strings.yml"), "data: []").unwrap();
+    write(dir.join("README.md"), "# SecLists").unwrap();
+    write(dir.join(".git/config"), "").unwrap();
+    write(dir.join("Fuzzing/SQLi/Generic-SQLi.txt"), "'").unwrap();
+    write(dir.join("Fuzzing/big.lst.gz"), "").unwrap();
+
+    let files = dictionary_files(&dir).unwrap();
+    remove_dir_all(&dir).unwrap();
+
+    let names: Vec<(&str, DictionaryFormat, bool)> = files.iter().map(|file| (file.name().as_str(), file.format(), file.compressed)).collect();
+    assert_eq!(names, vec![
+      ("Fuzzing/SQLi/Generic-SQLi", DictionaryFormat::Wordlist, false),
+      ("Fuzzing/big", DictionaryFormat::Wordlist, true),
+      ("strings", DictionaryFormat::Yaml, false),
+    ]);
+  }
+
+  #[test]
+  fn test_to_wordlist() {
+    let file = DictionaryFile::new(PathBuf::from("lists/Fuzzing/SQLi/Generic-SQLi.txt"), "Fuzzing/SQLi/Generic-SQLi.txt").unwrap();
+    let data = file.to_wordlist(b"# from SecLists\r\n' OR 1=1 -- 1\r\n\r\n   \n1' ORDER BY 1#\n");
+
+    assert_eq!(data.id(), "Fuzzing/SQLi/Generic-SQLi");
+    assert_eq!(data.description(), "wordlist lists/Fuzzing/SQLi/Generic-SQLi.txt");
+    assert_eq!(data.values(), &vec!["' OR 1=1 -- 1".to_string(), "1' ORDER BY 1#".to_string()]);
+    assert_eq!(data.tags(), &vec!["fuzzing".to_string(), "sqli".to_string()]);
+    assert_eq!(data.file(), "Fuzzing/SQLi/Generic-SQLi");
+  }
+
+  #[test]
+  fn test_read_gzip() {
+    let dir = temp_dir("wordlist-gzip");
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"admin\nroot\n").unwrap();
+    write(dir.join("users.txt.gz"), encoder.finish().unwrap()).unwrap();
+    write(dir.join("broken.txt.gz"), "not gzip").unwrap();
+
+    let files = dictionary_files(&dir).unwrap();
+    let broken = files[0].read_bytes();
+    let users = files[1].read_bytes().unwrap();
+    remove_dir_all(&dir).unwrap();
+
+    assert!(matches!(broken, Err(FuzzyError::Io { .. })));
+    assert_eq!(files[1].to_wordlist(&users).values(), &vec!["admin".to_string(), "root".to_string()]);
+  }
+
+  #[test]
+  fn test_read_latin1_wordlists() {
+    let dir = temp_dir("wordlist-latin1");
+    let latin1 = b"caf\xe9\nna\xefve\n";
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(latin1).unwrap();
+    write(dir.join("compressed.txt.gz"), encoder.finish().unwrap()).unwrap();
+    write(dir.join("plain.txt"), latin1).unwrap();
+
+    let files = dictionary_files(&dir).unwrap();
+    let wordlists: Vec<FuzzyData> = files.iter().map(|file| file.to_wordlist(&file.read_bytes().unwrap())).collect();
+    remove_dir_all(&dir).unwrap();
+
+    for wordlist in wordlists {
+      assert_eq!(wordlist.values(), &vec!["caf\u{fffd}".to_string(), "na\u{fffd}ve".to_string()]);
+    }
+  }
+
+  #[test]
+  fn test_dictionary_name() {
+    assert_eq!(dictionary_name("strings.yml"), "strings");
+    assert_eq!(dictionary_name("Fuzzing/SQLi/Generic-SQLi.txt"), "Fuzzing/SQLi/Generic-SQLi");
+    assert_eq!(dictionary_name("big.lst.gz"), "big");
+    assert_eq!(dictionary_name("Fuzzing/SQLi"), "Fuzzing/SQLi");
+  }
+}
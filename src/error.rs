@@ -0,0 +1,125 @@
+use std::fmt;
+use std::io;
+use tokio::sync::mpsc::error::SendError;
+use tokio::task::JoinError;
+use url::ParseError;
+use crate::fuzzer::matcher::MatcherError;
+
+/// Errors that stop a run, or a path of it, instead of being reported as a result.
+#[derive(Debug)]
+pub enum FuzzyError {
+  Io { path: String, source: io::Error },
+  Yaml { path: String, source: serde_yaml::Error },
+  InvalidUrl { endpoint: String, source: ParseError },
+  InvalidExpectations { endpoint: String, source: MatcherError },
+  /// A pattern of an error signature that is not a valid regular expression.
+  InvalidSignature { id: String, source: regex::Error },
+  /// Settings of the input file that a run cannot start with, e.g. a concurrency of 0.
+  InvalidSettings { message: String },
+  /// A document that could be parsed but not turned into an input.
+  Import { path: String, message: String },
+  Request(RequestError),
+  /// The results channel was closed before every result was sent, e.g. because writing the output failed.
+  ChannelClosed,
+  Task(JoinError),
+}
+
+impl FuzzyError {
+  pub fn io(path: &str, source: io::Error) -> Self {
+    FuzzyError::Io { path: path.to_string(), source }
+  }
+
+  pub fn yaml(path: &str, source: serde_yaml::Error) -> Self {
+    FuzzyError::Yaml { path: path.to_string(), source }
+  }
+}
+
+impl fmt::Display for FuzzyError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      FuzzyError::Io { path, source } => write!(f, "{}: {}", path, source),
+      FuzzyError::Yaml { path, source } => write!(f, "{}: {}", path, source),
+      FuzzyError::InvalidUrl { endpoint, source } => write!(f, "invalid url for {}: {}", endpoint, source),
+      FuzzyError::InvalidExpectations { endpoint, source } => write!(f, "invalid expectations for {}: {}", endpoint, source),
+      FuzzyError::InvalidSignature { id, source } => write!(f, "invalid pattern for signature {}: {}", id, source),
+      FuzzyError::InvalidSettings { message } => write!(f, "invalid settings: {}", message),
+      FuzzyError::Import { path, message } => write!(f, "{}: {}", path, message),
+      FuzzyError::Request(err) => write!(f, "{}", err),
+      FuzzyError::ChannelClosed => write!(f, "results channel closed before fuzzing finished"),
+      FuzzyError::Task(err) => write!(f, "fuzzing task failed: {}", err),
+    }
+  }
+}
+
+impl std::error::Error for FuzzyError {
+  fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    match self {
+      FuzzyError::Io { source, .. } => Some(source),
+      FuzzyError::Yaml { source, .. } => Some(source),
+      FuzzyError::InvalidUrl { source, .. } => Some(source),
+      FuzzyError::InvalidSignature { source, .. } => Some(source),
+      FuzzyError::Request(err) => Some(err),
+      FuzzyError::Task(err) => Some(err),
+      FuzzyError::InvalidExpectations { .. } | FuzzyError::InvalidSettings { .. } | FuzzyError::Import { .. } | FuzzyError::ChannelClosed => None,
+    }
+  }
+}
+
+impl From<RequestError> for FuzzyError {
+  fn from(err: RequestError) -> Self {
+    FuzzyError::Request(err)
+  }
+}
+
+impl From<JoinError> for FuzzyError {
+  fn from(err: JoinError) -> Self {
+    FuzzyError::Task(err)
+  }
+}
+
+impl<T> From<SendError<T>> for FuzzyError {
+  fn from(_: SendError<T>) -> Self {
+    FuzzyError::ChannelClosed
+  }
+}
+
+/// Why a single fuzzed request could not be built, reported as its result instead of stopping the run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RequestError {
+  InvalidHeaderName { name: String },
+  InvalidHeaderValue { name: String, value: String },
+}
+
+impl fmt::Display for RequestError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      RequestError::InvalidHeaderName { name } => write!(f, "invalid header name: {:?}", name),
+      RequestError::InvalidHeaderValue { name, value } => write!(f, "invalid value for header {}: {:?}", name, value),
+    }
+  }
+}
+
+impl std::error::Error for RequestError {}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_display() {
+    let err = FuzzyError::io("input.yml", io::Error::new(io::ErrorKind::NotFound, "not found"));
+    assert_eq!(err.to_string(), "input.yml: not found");
+
+    let err = FuzzyError::from(RequestError::InvalidHeaderValue { name: "X-Test".into(), value: "a\nb".into() });
+    assert_eq!(err.to_string(), "invalid value for header X-Test: \"a\\nb\"");
+  }
+
+  #[tokio::test]
+  async fn test_closed_channel() {
+    let (tx, rx) = tokio::sync::mpsc::channel::<u8>(1);
+    drop(rx);
+
+    let err: FuzzyError = tx.send(1).await.unwrap_err().into();
+    assert!(matches!(err, FuzzyError::ChannelClosed));
+  }
+}
@@ -0,0 +1,462 @@
+use std::sync::Arc;
+use serde::Serialize;
+use crate::domain::dictionary::{FuzzyData, FuzzyDictionary, Metadata};
+use crate::domain::input::{AttackStrategy, FuzzPoint};
+use crate::domain::selector::Selection;
+use crate::domain::transform::{apply_chain, Transform};
+
+/// A dictionary value placed into a named placeholder of a request.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct InjectedPayload {
+  point: String,
+  value: String,
+  id: String,
+  description: String,
+  /// The transform chain the dictionary value went through before being sent as `value`.
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  transforms: Vec<Transform>,
+  /// Shared by every value of the dictionary, and written once per result rather than per payload.
+  #[serde(skip)]
+  metadata: Arc<Metadata>,
+}
+
+impl InjectedPayload {
+  pub fn new(point: String, value: String, id: String, description: String) -> Self {
+    Self { point, value, id, description, transforms: Vec::new(), metadata: Arc::default() }
+  }
+
+  pub fn with_transforms(mut self, transforms: Vec<Transform>) -> Self {
+    self.transforms = transforms;
+    self
+  }
+
+  pub fn with_metadata(mut self, metadata: Arc<Metadata>) -> Self {
+    self.metadata = metadata;
+    self
+  }
+
+  pub fn point(&self) -> &String {
+    &self.point
+  }
+
+  pub fn value(&self) -> &String {
+    &self.value
+  }
+
+  pub fn id(&self) -> &String {
+    &self.id
+  }
+
+  pub fn description(&self) -> &String {
+    &self.description
+  }
+
+  pub fn transforms(&self) -> &Vec<Transform> {
+    &self.transforms
+  }
+
+  pub fn metadata(&self) -> &Metadata {
+    &self.metadata
+  }
+}
+
+/// A placeholder of a request with the dictionaries it is fuzzed with. Payloads are rendered from the dictionaries
+/// when a request needs them, so a point takes the same memory whatever the size of its wordlists.
+#[derive(Clone, Debug)]
+pub struct AttackPoint<'a> {
+  name: String,
+  default: String,
+  /// The selected dictionaries, with the metadata shared by their payloads.
+  sources: Vec<(&'a FuzzyData, Arc<Metadata>)>,
+  /// Number of values in the sources before each one, to find the source of a payload index.
+  offsets: Vec<usize>,
+  values: usize,
+  chains: Vec<Vec<Transform>>,
+}
+
+impl<'a> AttackPoint<'a> {
+  /// Takes the payloads of the dictionaries selected for the path, narrowed by the selectors of the fuzz point, with a
+  /// variant of each value per transform chain of the fuzz point.
+  pub fn new(name: &str, fuzz_point: Option<&FuzzPoint>, dict: &'a FuzzyDictionary, selection: &Selection) -> Self {
+    let selection = match fuzz_point {
+      Some(point) => selection.narrow(point.include(), point.exclude()),
+      None => selection.clone(),
+    };
+    let chains = match fuzz_point {
+      Some(point) if !point.transforms().is_empty() => point.transforms().clone(),
+      _ => vec![Vec::new()],
+    };
+    let sources: Vec<(&FuzzyData, Arc<Metadata>)> = dict.data()
+      .iter()
+      .filter(|item| selection.selects(item))
+      .map(|item| (item, Arc::new(item.metadata())))
+      .collect();
+
+    let mut offsets = Vec::with_capacity(sources.len());
+    let mut values = 0;
+    for (item, _) in &sources {
+      offsets.push(values);
+      values += item.values().len();
+    }
+
+    Self {
+      name: name.to_string(),
+      default: fuzz_point.map(|point| point.default().clone()).unwrap_or_default(),
+      sources,
+      offsets,
+      values,
+      chains,
+    }
+  }
+
+  pub fn name(&self) -> &String {
+    &self.name
+  }
+
+  pub fn default(&self) -> &String {
+    &self.default
+  }
+
+  /// Number of payload indices, one per transform chain of every value, including the variants left out.
+  pub fn len(&self) -> usize {
+    self.values * self.chains.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  /// The source of the value of a payload index.
+  fn source(&self, index: usize) -> usize {
+    self.offsets.partition_point(|offset| *offset <= index / self.chains.len()) - 1
+  }
+
+  /// Renders the payload at an index below `len`, `None` when its variant equals the one of an earlier chain for the
+  /// same value, e.g. url encoding a value without special characters.
+  pub fn payload(&self, index: usize) -> Option<InjectedPayload> {
+    let source = self.source(index);
+    let (item, metadata) = &self.sources[source];
+    let value = &item.values()[index / self.chains.len() - self.offsets[source]];
+    let chain = &self.chains[index % self.chains.len()];
+
+    let variant = apply_chain(chain, value);
+    if self.chains[..index % self.chains.len()].iter().any(|earlier| apply_chain(earlier, value) == variant) {
+      return None;
+    }
+
+    Some(InjectedPayload::new(self.name.clone(), variant, item.id().clone(), item.description().clone())
+      .with_transforms(chain.clone())
+      .with_metadata(metadata.clone()))
+  }
+
+  /// The first payload from the index on that is not left out, with its index.
+  fn next_payload(&self, from: usize) -> Option<(usize, InjectedPayload)> {
+    (from..self.len()).find_map(|index| self.payload(index).map(|payload| (index, payload)))
+  }
+
+  /// Every payload of the point, in order.
+  pub fn payloads(&self) -> impl Iterator<Item = InjectedPayload> + '_ {
+    (0..self.len()).filter_map(|index| self.payload(index))
+  }
+
+  /// Whether an earlier point sends the same values, which battering ram then does not send again.
+  fn shares_source(&self, source: usize, earlier: &[AttackPoint]) -> bool {
+    let item = self.sources[source].0;
+    earlier.iter().any(|point| {
+      point.chains == self.chains && point.sources.iter().any(|(other, _)| std::ptr::eq(*other, item))
+    })
+  }
+}
+
+/// Returns the payloads injected by each request of the attack, points missing from a request keep their default.
+/// Requests are generated one at a time, so a cluster bomb over large wordlists is never held in memory.
+pub fn combinations<'a>(strategy: &AttackStrategy, points: &'a [AttackPoint<'a>]) -> Combinations<'a> {
+  let shared = match strategy {
+    AttackStrategy::BatteringRam => points
+      .iter()
+      .enumerate()
+      .map(|(index, point)| (0..point.sources.len()).map(|source| point.shares_source(source, &points[..index])).collect())
+      .collect(),
+    _ => Vec::new(),
+  };
+
+  Combinations {
+    strategy: strategy.clone(),
+    points,
+    indices: vec![0; points.len()],
+    point: 0,
+    shared,
+    current: None,
+    done: points.is_empty(),
+  }
+}
+
+/// Lazily iterates over the payloads of each request of an attack.
+///
+/// Battering ram sends a dictionary once when several points are fuzzed with it, but does not remember the values it
+/// sent, so a value found in several dictionaries is sent once for each.
+pub struct Combinations<'a> {
+  strategy: AttackStrategy,
+  points: &'a [AttackPoint<'a>],
+  /// The next payload index of each point, only the first is used by sniper and battering ram.
+  indices: Vec<usize>,
+  /// The point whose payloads sniper and battering ram are going through.
+  point: usize,
+  /// Whether battering ram skips each source of each point, as an earlier point already sent it.
+  shared: Vec<Vec<bool>>,
+  /// The cluster bomb combination to send next, with the payload index of each point.
+  current: Option<Vec<(usize, InjectedPayload)>>,
+  done: bool,
+}
+
+impl<'a> Combinations<'a> {
+  /// Moves to the next payload of the current point, or to the first payload of the next point with any.
+  fn next_payload(&mut self) -> Option<InjectedPayload> {
+    while let Some(point) = self.points.get(self.point) {
+      if let Some((index, payload)) = point.next_payload(self.indices[0]) {
+        self.indices[0] = index + 1;
+        return Some(payload);
+      }
+      self.point += 1;
+      self.indices[0] = 0;
+    }
+
+    None
+  }
+
+  /// Counts the cluster bomb indices up like an odometer, the last point moving fastest.
+  fn advance(&mut self, current: &mut [(usize, InjectedPayload)]) -> bool {
+    for (point, position) in self.points.iter().zip(current.iter_mut()).rev() {
+      match point.next_payload(position.0 + 1) {
+        Some(next) => {
+          *position = next;
+          return true;
+        }
+        None => *position = point.next_payload(0).expect("a point of a started cluster bomb has payloads"),
+      }
+    }
+
+    false
+  }
+}
+
+impl<'a> Iterator for Combinations<'a> {
+  type Item = Vec<InjectedPayload>;
+
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.done {
+      return None;
+    }
+
+    match self.strategy {
+      AttackStrategy::Sniper => self.next_payload().map(|payload| vec![payload]),
+      AttackStrategy::BatteringRam => {
+        while let Some(payload) = self.next_payload() {
+          let point = &self.points[self.point];
+          if self.shared[self.point][point.source(self.indices[0] - 1)] {
+            continue;
+          }
+
+          return Some(self.points
+            .iter()
+            .map(|point| InjectedPayload { point: point.name.clone(), ..payload.clone() })
+            .collect());
+        }
+
+        None
+      }
+      AttackStrategy::Pitchfork => {
+        let points = self.points;
+        let indices = &mut self.indices;
+        let combination: Option<Vec<InjectedPayload>> = points
+          .iter()
+          .zip(indices.iter_mut())
+          .map(|(point, index)| {
+            let (found, payload) = point.next_payload(*index)?;
+            *index = found + 1;
+            Some(payload)
+          })
+          .collect();
+        self.done = combination.is_none();
+        combination
+      }
+      AttackStrategy::ClusterBomb => {
+        let mut current = match self.current.take() {
+          Some(current) => current,
+          None => match self.points.iter().map(|point| point.next_payload(0)).collect() {
+            Some(current) => current,
+            None => {
+              self.done = true;
+              return None;
+            }
+          },
+        };
+
+        let combination = current.iter().map(|(_, payload)| payload.clone()).collect();
+        if self.advance(&mut current) {
+          self.current = Some(current);
+        } else {
+          self.done = true;
+        }
+        Some(combination)
+      }
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::domain::dictionary::{FuzzyData, Severity};
+  use super::*;
+
+  fn sample_dictionary() -> FuzzyDictionary {
+    serde_yaml::from_str(r#"
+      data:
+        - id: numbers
+          description: numbers
+          severity: low
+          values: ["1", "2"]
+        - id: letters
+          description: letters
+          tags: [alphabet]
+          values: ["a", "b", "c"]
+      "#).unwrap()
+  }
+
+  fn sample_points(dict: &FuzzyDictionary) -> Vec<AttackPoint<'_>> {
+    vec![
+      AttackPoint::new("fuzz1", Some(&FuzzPoint::new("fuzz1".into(), vec!["numbers".into()], "0".into())), dict, &Selection::default()),
+      AttackPoint::new("fuzz2", Some(&FuzzPoint::new("fuzz2".into(), vec!["letters".into()], "z".into())), dict, &Selection::default()),
+    ]
+  }
+
+  fn values(combinations: impl Iterator<Item = Vec<InjectedPayload>>) -> Vec<Vec<(String, String)>> {
+    combinations
+      .map(|combination| combination.into_iter().map(|payload| (payload.point, payload.value)).collect())
+      .collect()
+  }
+
+  fn pairs(expected: &[&[(&str, &str)]]) -> Vec<Vec<(String, String)>> {
+    expected
+      .iter()
+      .map(|combination| combination.iter().map(|(point, value)| (point.to_string(), value.to_string())).collect())
+      .collect()
+  }
+
+  #[test]
+  fn test_attack_point_includes_dictionaries() {
+    let dict = sample_dictionary();
+    let all = AttackPoint::new("fuzz", None, &dict, &Selection::default());
+    let numbers = AttackPoint::new("fuzz", Some(&FuzzPoint::new("fuzz".into(), vec!["numbers".into()], "".into())), &dict, &Selection::default());
+    let path_selection = Selection::new(&["tag:alphabet".into()], &[]);
+    let letters = AttackPoint::new("fuzz", None, &dict, &path_selection);
+    let without_numbers = AttackPoint::new("fuzz", Some(&FuzzPoint::new("fuzz".into(), vec![], "".into()).with_exclude(vec!["numbers".into()])), &dict, &Selection::default());
+
+    assert_eq!(all.payloads().count(), 5);
+    assert_eq!(all.default(), "");
+    let numbers: Vec<InjectedPayload> = numbers.payloads().collect();
+    assert_eq!(numbers.len(), 2);
+    assert_eq!(numbers[0].id(), "numbers");
+    assert_eq!(numbers[1].metadata().severity(), Some(Severity::Low));
+    assert_eq!(letters.payloads().count(), 3);
+    assert!(without_numbers.payloads().all(|payload| payload.id() == "letters"));
+  }
+
+  #[test]
+  fn test_attack_point_applies_transforms() {
+    let dict = sample_dictionary();
+    let fuzz_point = FuzzPoint::new("fuzz".into(), vec!["letters".into()], "".into()).with_transforms(vec![
+      vec![],
+      vec![Transform::Upper],
+      vec![Transform::Url],
+      vec![Transform::Prefix("'".into()), Transform::Hex],
+    ]);
+    let point = AttackPoint::new("fuzz", Some(&fuzz_point), &dict, &Selection::default());
+
+    let payloads: Vec<InjectedPayload> = point.payloads().collect();
+    let values: Vec<&String> = payloads.iter().map(|payload| payload.value()).collect();
+    // url encoding leaves letters as they are, so it adds no variant
+    assert_eq!(values, vec!["a", "A", "2761", "b", "B", "2762", "c", "C", "2763"]);
+    assert_eq!(payloads[1].transforms(), &vec![Transform::Upper]);
+    assert_eq!(payloads[1].id(), "letters");
+  }
+
+  #[test]
+  fn test_sniper() {
+    assert_eq!(values(combinations(&AttackStrategy::Sniper, &sample_points(&sample_dictionary()))), pairs(&[
+      &[("fuzz1", "1")], &[("fuzz1", "2")], &[("fuzz2", "a")], &[("fuzz2", "b")], &[("fuzz2", "c")],
+    ]));
+  }
+
+  #[test]
+  fn test_battering_ram() {
+    assert_eq!(values(combinations(&AttackStrategy::BatteringRam, &sample_points(&sample_dictionary()))), pairs(&[
+      &[("fuzz1", "1"), ("fuzz2", "1")],
+      &[("fuzz1", "2"), ("fuzz2", "2")],
+      &[("fuzz1", "a"), ("fuzz2", "a")],
+      &[("fuzz1", "b"), ("fuzz2", "b")],
+      &[("fuzz1", "c"), ("fuzz2", "c")],
+    ]));
+  }
+
+  #[test]
+  fn test_battering_ram_sends_a_shared_dictionary_once() {
+    let dict = sample_dictionary();
+    let points = vec![
+      AttackPoint::new("fuzz1", None, &dict, &Selection::default()),
+      AttackPoint::new("fuzz2", Some(&FuzzPoint::new("fuzz2".into(), vec!["numbers".into()], "".into())), &dict, &Selection::default()),
+    ];
+
+    assert_eq!(values(combinations(&AttackStrategy::BatteringRam, &points)).len(), 5);
+  }
+
+  #[test]
+  fn test_pitchfork() {
+    assert_eq!(values(combinations(&AttackStrategy::Pitchfork, &sample_points(&sample_dictionary()))), pairs(&[
+      &[("fuzz1", "1"), ("fuzz2", "a")],
+      &[("fuzz1", "2"), ("fuzz2", "b")],
+    ]));
+  }
+
+  #[test]
+  fn test_cluster_bomb() {
+    assert_eq!(values(combinations(&AttackStrategy::ClusterBomb, &sample_points(&sample_dictionary()))), pairs(&[
+      &[("fuzz1", "1"), ("fuzz2", "a")],
+      &[("fuzz1", "1"), ("fuzz2", "b")],
+      &[("fuzz1", "1"), ("fuzz2", "c")],
+      &[("fuzz1", "2"), ("fuzz2", "a")],
+      &[("fuzz1", "2"), ("fuzz2", "b")],
+      &[("fuzz1", "2"), ("fuzz2", "c")],
+    ]));
+  }
+
+  #[test]
+  fn test_cluster_bomb_is_generated_lazily() {
+    let dict = FuzzyDictionary::new(vec![FuzzyData::new("numbers".into(), "numbers".into(), (0..1000).map(|value| value.to_string()).collect())]);
+    let points: Vec<AttackPoint> = ["fuzz1", "fuzz2", "fuzz3"]
+      .iter()
+      .map(|name| AttackPoint::new(name, None, &dict, &Selection::default()))
+      .collect();
+
+    let mut combinations = combinations(&AttackStrategy::ClusterBomb, &points);
+    assert_eq!(values(combinations.by_ref().take(2)), pairs(&[
+      &[("fuzz1", "0"), ("fuzz2", "0"), ("fuzz3", "0")],
+      &[("fuzz1", "0"), ("fuzz2", "0"), ("fuzz3", "1")],
+    ]));
+    assert_eq!(combinations.nth(998).unwrap()[1].value(), "1");
+  }
+
+  #[test]
+  fn test_cluster_bomb_with_empty_point() {
+    let dict = sample_dictionary();
+    let mut points = sample_points(&dict);
+    points.push(AttackPoint::new("fuzz3", Some(&FuzzPoint::new("fuzz3".into(), vec!["missing".into()], "".into())), &dict, &Selection::default()));
+
+    assert!(combinations(&AttackStrategy::ClusterBomb, &points).next().is_none());
+  }
+
+  #[test]
+  fn test_no_points_no_combinations() {
+    assert!(combinations(&AttackStrategy::ClusterBomb, &[]).next().is_none());
+  }
+}
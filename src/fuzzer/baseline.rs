@@ -0,0 +1,307 @@
+use std::collections::BTreeSet;
+use std::time::Duration;
+use reqwest::header::{HeaderMap, CONTENT_TYPE};
+use crate::fuzzer::fuzzy::{CapturedResponse, FailureReason};
+
+/// A numeric feature only counts as deviating once it is this many standard deviations away from the baseline.
+const DEVIATION_LIMIT: f64 = 3.0;
+
+/// Headers whose presence depends on the server or connection rather than the request.
+const VOLATILE_HEADERS: [&str; 5] = ["date", "age", "expires", "last-modified", "set-cookie"];
+
+/// What is compared between a fuzzed response and the baseline responses of its path.
+#[derive(Clone, Debug, PartialEq)]
+struct Features {
+  status: u16,
+  length: f64,
+  words: f64,
+  lines: f64,
+  content_type: Option<String>,
+  header_names: BTreeSet<String>,
+  latency_ms: f64,
+}
+
+impl Features {
+  fn new(response: &CapturedResponse) -> Self {
+    let body = response.body();
+
+    Self {
+      status: response.status(),
+      length: body.len() as f64,
+      words: body.split_whitespace().count() as f64,
+      lines: body.lines().count() as f64,
+      content_type: content_type(response.headers()),
+      header_names: response.headers().keys()
+        .map(|name| name.as_str().to_string())
+        .filter(|name| !VOLATILE_HEADERS.contains(&name.as_str()))
+        .collect(),
+      latency_ms: response.latency().as_secs_f64() * 1000.0,
+    }
+  }
+}
+
+/// The media type without parameters, e.g. `application/json` for `application/json; charset=utf-8`.
+fn content_type(headers: &HeaderMap) -> Option<String> {
+  headers.get(CONTENT_TYPE)
+    .and_then(|value| value.to_str().ok())
+    .map(|value| value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase())
+}
+
+/// A numeric feature with the smallest spread it is compared with, see [`Distribution::new`].
+struct NumericFeature {
+  name: &'static str,
+  value: fn(&Features) -> f64,
+  relative: f64,
+  absolute: f64,
+}
+
+const NUMERIC_FEATURES: [NumericFeature; 4] = [
+  NumericFeature { name: "length", value: |features| features.length, relative: 0.05, absolute: 16.0 },
+  NumericFeature { name: "words", value: |features| features.words, relative: 0.05, absolute: 2.0 },
+  NumericFeature { name: "lines", value: |features| features.lines, relative: 0.05, absolute: 1.0 },
+  NumericFeature { name: "latency ms", value: |features| features.latency_ms, relative: 0.5, absolute: 250.0 },
+];
+
+/// Mean and standard deviation of a feature, with a tolerance so that a steady baseline does not flag every change.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Distribution {
+  mean: f64,
+  deviation: f64,
+}
+
+impl Distribution {
+  /// `relative` is the smallest spread as a fraction of the mean, `absolute` the smallest spread overall.
+  fn new(samples: &[f64], relative: f64, absolute: f64) -> Self {
+    let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+    let variance = samples.iter().map(|sample| (sample - mean).powi(2)).sum::<f64>() / samples.len() as f64;
+    let deviation = variance.sqrt().max(mean * relative).max(absolute);
+
+    Self { mean, deviation }
+  }
+
+  /// How many standard deviations the value is from the mean, in units of the deviation limit.
+  fn score(&self, value: f64) -> f64 {
+    (value - self.mean).abs() / self.deviation / DEVIATION_LIMIT
+  }
+}
+
+/// Why a response stands out from the baseline, with how much it adds to the anomaly score.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deviation {
+  score: f64,
+  detail: String,
+}
+
+impl Deviation {
+  pub fn score(&self) -> f64 {
+    self.score
+  }
+
+  pub fn detail(&self) -> &String {
+    &self.detail
+  }
+}
+
+/// Responses to benign requests of one path, sent before fuzzing it, that fuzzed responses are scored against.
+///
+/// Status, content type and the set of header names deviate when they were never seen in the baseline. Length,
+/// word, line counts and latency are compared with the baseline responses that had the same status, or all of them
+/// when the status is new, and deviate once they are more than three standard deviations away.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Baseline {
+  responses: Vec<Features>,
+  threshold: f64,
+  signatures: Vec<String>,
+}
+
+impl Baseline {
+  /// Returns `None` without responses, a response is anomalous once its deviations add up to `threshold`.
+  pub fn new(responses: &[CapturedResponse], threshold: f64) -> Option<Self> {
+    if responses.is_empty() {
+      return None;
+    }
+
+    Some(Self { responses: responses.iter().map(Features::new).collect(), threshold, signatures: Vec::new() })
+  }
+
+  /// Ids of the error signatures found in the baseline responses.
+  pub fn with_signatures(mut self, signatures: Vec<String>) -> Self {
+    self.signatures = signatures;
+    self
+  }
+
+  pub fn signatures(&self) -> &Vec<String> {
+    &self.signatures
+  }
+
+  pub fn median_latency(&self) -> Duration {
+    let mut latencies: Vec<f64> = self.responses.iter().map(|features| features.latency_ms).collect();
+    latencies.sort_by(f64::total_cmp);
+
+    let middle = latencies.len() / 2;
+    let median = if latencies.len().is_multiple_of(2) { (latencies[middle - 1] + latencies[middle]) / 2.0 } else { latencies[middle] };
+    Duration::from_secs_f64(median / 1000.0)
+  }
+
+  pub fn deviations(&self, response: &CapturedResponse) -> Vec<Deviation> {
+    let actual = Features::new(response);
+    let mut deviations = Vec::new();
+
+    let statuses: BTreeSet<u16> = self.responses.iter().map(|features| features.status).collect();
+    if !statuses.contains(&actual.status) {
+      deviations.push(Deviation { score: 1.0, detail: format!("status {} not in baseline {:?}", actual.status, statuses) });
+    }
+
+    let content_types: BTreeSet<&Option<String>> = self.responses.iter().map(|features| &features.content_type).collect();
+    if !content_types.contains(&actual.content_type) {
+      deviations.push(Deviation {
+        score: 1.0,
+        detail: format!("content type {} not in baseline", actual.content_type.as_deref().unwrap_or("none")),
+      });
+    }
+
+    deviations.extend(self.header_deviation(&actual));
+
+    let same_status: Vec<&Features> = self.responses.iter().filter(|features| features.status == actual.status).collect();
+    let compared = if same_status.is_empty() { self.responses.iter().collect() } else { same_status };
+
+    for feature in &NUMERIC_FEATURES {
+      let samples: Vec<f64> = compared.iter().map(|features| (feature.value)(features)).collect();
+      let distribution = Distribution::new(&samples, feature.relative, feature.absolute);
+      let value = (feature.value)(&actual);
+      let score = distribution.score(value);
+
+      if score >= 1.0 {
+        let detail = format!("{} {:.0} outside baseline {:.0} ± {:.0}", feature.name, value, distribution.mean, distribution.deviation);
+        deviations.push(Deviation { score, detail });
+      }
+    }
+
+    deviations
+  }
+
+  /// Headers sent in every baseline response but missing, or never sent in the baseline but present.
+  fn header_deviation(&self, actual: &Features) -> Option<Deviation> {
+    let always: BTreeSet<&String> = self.responses[0].header_names.iter()
+      .filter(|name| self.responses.iter().all(|features| features.header_names.contains(*name)))
+      .collect();
+    let ever: BTreeSet<&String> = self.responses.iter().flat_map(|features| &features.header_names).collect();
+
+    let missing: Vec<&str> = always.iter().filter(|name| !actual.header_names.contains(**name)).map(|name| name.as_str()).collect();
+    let added: Vec<&str> = actual.header_names.iter().filter(|name| !ever.contains(name)).map(|name| name.as_str()).collect();
+
+    if missing.is_empty() && added.is_empty() {
+      return None;
+    }
+
+    let mut changes = Vec::new();
+    if !added.is_empty() {
+      changes.push(format!("added {}", added.join(", ")));
+    }
+    if !missing.is_empty() {
+      changes.push(format!("missing {}", missing.join(", ")));
+    }
+
+    Some(Deviation { score: 0.5, detail: format!("headers {}", changes.join(", ")) })
+  }
+
+  /// Reports the response as anomalous when its deviations add up to the threshold.
+  pub fn check(&self, response: &CapturedResponse) -> Option<FailureReason> {
+    let deviations = self.deviations(response);
+    let score: f64 = deviations.iter().map(Deviation::score).sum();
+
+    if score >= self.threshold {
+      Some(FailureReason::Anomaly {
+        score: (score * 100.0).round() / 100.0,
+        deviations: deviations.into_iter().map(|deviation| deviation.detail).collect(),
+      })
+    } else {
+      None
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use reqwest::header::HeaderValue;
+  use super::*;
+
+  fn response(status: u16, content_type: &str, body: &str, latency_ms: u64) -> CapturedResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+    headers.insert("date", HeaderValue::from_static("Sun, 17 Sep 2023 19:28:53 GMT"));
+    CapturedResponse::new(status, headers, body.into(), Duration::from_millis(latency_ms))
+  }
+
+  fn sample_baseline() -> Baseline {
+    Baseline::new(&[
+      response(200, "application/json; charset=utf-8", r#"{"id": 1, "name": "bob"}"#, 20),
+      response(400, "application/json", r#"{"error": "invalid id"}"#, 15),
+      response(400, "application/json", r#"{"error": "invalid id"}"#, 18),
+    ], 1.0).unwrap()
+  }
+
+  #[test]
+  fn test_responses_like_the_baseline_are_not_anomalous() {
+    let baseline = sample_baseline();
+
+    assert_eq!(baseline.check(&response(400, "application/json", r#"{"error": "invalid id"}"#, 30)), None);
+    assert_eq!(baseline.check(&response(200, "application/json", r#"{"id": 2, "name": "alice"}"#, 25)), None);
+  }
+
+  #[test]
+  fn test_new_status_and_content_type_are_anomalous() {
+    let baseline = sample_baseline();
+
+    match baseline.check(&response(500, "text/html", "<h1>Internal Server Error</h1>", 20)) {
+      Some(FailureReason::Anomaly { score, deviations }) => {
+        assert!(score >= 2.0);
+        assert_eq!(deviations[0], "status 500 not in baseline {200, 400}");
+        assert_eq!(deviations[1], "content type text/html not in baseline");
+      }
+      reason => panic!("expected an anomaly, got {:?}", reason),
+    }
+  }
+
+  #[test]
+  fn test_length_is_compared_with_the_same_status() {
+    let baseline = sample_baseline();
+    let stack_trace = format!(r#"{{"error": "invalid id", "trace": "{}"}}"#, "at Controller.java:42 ".repeat(20));
+
+    let deviations = baseline.deviations(&response(400, "application/json", &stack_trace, 15));
+    let details: Vec<&String> = deviations.iter().map(Deviation::detail).collect();
+    assert!(details[0].starts_with("length "));
+    assert!(details[1].starts_with("words "));
+  }
+
+  #[test]
+  fn test_header_changes() {
+    let baseline = sample_baseline();
+    let mut headers = HeaderMap::new();
+    headers.insert("x-debug", HeaderValue::from_static("1"));
+    let changed = CapturedResponse::new(200, headers, r#"{"id": 1, "name": "bob"}"#.into(), Duration::from_millis(20));
+
+    let deviations = baseline.deviations(&changed);
+    assert_eq!(deviations.last().unwrap().detail(), "headers added x-debug, missing content-type");
+    assert_eq!(deviations.last().unwrap().score(), 0.5);
+  }
+
+  #[test]
+  fn test_distribution_tolerance() {
+    let distribution = Distribution::new(&[100.0, 100.0], 0.05, 1.0);
+
+    assert_eq!(distribution.deviation, 5.0);
+    assert!(distribution.score(110.0) < 1.0);
+    assert!(distribution.score(120.0) >= 1.0);
+  }
+
+  #[test]
+  fn test_median_latency() {
+    assert_eq!(sample_baseline().median_latency(), Duration::from_millis(18));
+  }
+
+  #[test]
+  fn test_empty_baseline() {
+    assert_eq!(Baseline::new(&[], 1.0), None);
+  }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use url::form_urlencoded;
+use crate::domain::placeholder::{replace_placeholders, replace_placeholders_at};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BodyFormat {
+  Json,
+  Form,
+  Xml,
+  Raw,
+}
+
+impl BodyFormat {
+  pub fn from_content_type(content_type: Option<&str>) -> Self {
+    let content_type = match content_type {
+      Some(content_type) => content_type.to_ascii_lowercase(),
+      None => return BodyFormat::Raw,
+    };
+
+    if content_type.contains("json") {
+      BodyFormat::Json
+    } else if content_type.contains("x-www-form-urlencoded") {
+      BodyFormat::Form
+    } else if content_type.contains("xml") {
+      BodyFormat::Xml
+    } else {
+      BodyFormat::Raw
+    }
+  }
+
+  /// Escapes a value so it can be placed inside a document of this format without breaking its syntax,
+  /// e.g. inside a JSON string the value is escaped but not wrapped in quotes.
+  pub fn escape(&self, value: &str) -> String {
+    match self {
+      BodyFormat::Json => {
+        let quoted = serde_json::to_string(value).expect("a string is always serializable");
+        quoted[1..quoted.len() - 1].to_string()
+      }
+      BodyFormat::Form => form_urlencoded::byte_serialize(value.as_bytes()).collect(),
+      BodyFormat::Xml => value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;"),
+      BodyFormat::Raw => value.to_string(),
+    }
+  }
+}
+
+/// Replaces the placeholders of a body, escaping values for its format. In JSON a placeholder outside of a string,
+/// e.g. `"age": {fuzz_age}`, keeps the type of the field: values that are valid JSON are written as they are and the
+/// others as strings.
+pub fn render_body(template: &str, format: BodyFormat, values: &HashMap<String, String>) -> String {
+  if format != BodyFormat::Json {
+    return replace_placeholders(template, values, |value| format.escape(value));
+  }
+
+  let strings = json_strings(template);
+  replace_placeholders_at(template, values, |offset, value| {
+    if strings.iter().any(|(start, end)| *start < offset && offset < *end) {
+      format.escape(value)
+    } else if serde_json::from_str::<serde_json::Value>(value).is_ok() {
+      value.to_string()
+    } else {
+      serde_json::to_string(value).expect("a string is always serializable")
+    }
+  })
+}
+
+/// Byte offsets of the opening and closing quotes of every string in a JSON document.
+fn json_strings(json: &str) -> Vec<(usize, usize)> {
+  let mut strings = Vec::new();
+  let mut start = None;
+  let mut escaped = false;
+
+  for (offset, byte) in json.bytes().enumerate() {
+    match (start, byte) {
+      (Some(_), _) if escaped => escaped = false,
+      (Some(_), b'\\') => escaped = true,
+      (Some(opening), b'"') => {
+        strings.push((opening, offset));
+        start = None;
+      }
+      (None, b'"') => start = Some(offset),
+      _ => {}
+    }
+  }
+
+  strings
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn values(value: &str) -> HashMap<String, String> {
+    HashMap::from([("fuzz".to_string(), value.to_string())])
+  }
+
+  #[test]
+  fn test_format_from_content_type() {
+    assert_eq!(BodyFormat::from_content_type(Some("application/json; charset=utf-8")), BodyFormat::Json);
+    assert_eq!(BodyFormat::from_content_type(Some("application/vnd.api+json")), BodyFormat::Json);
+    assert_eq!(BodyFormat::from_content_type(Some("application/x-www-form-urlencoded")), BodyFormat::Form);
+    assert_eq!(BodyFormat::from_content_type(Some("text/xml")), BodyFormat::Xml);
+    assert_eq!(BodyFormat::from_content_type(Some("text/plain")), BodyFormat::Raw);
+    assert_eq!(BodyFormat::from_content_type(None), BodyFormat::Raw);
+  }
+
+  #[test]
+  fn test_render_json_body_escapes_quotes_and_backslashes() {
+    let rendered = render_body(r#"{ "user": "{fuzz}" }"#, BodyFormat::Json, &values(r#"" OR "1"="1 \"#));
+
+    assert_eq!(rendered, r#"{ "user": "\" OR \"1\"=\"1 \\" }"#);
+    let parsed: serde_json::Value = serde_json::from_str(&rendered).unwrap();
+    assert_eq!(parsed["user"], r#"" OR "1"="1 \"#);
+  }
+
+  #[test]
+  fn test_render_json_body_keeps_unquoted_placeholders_typed() {
+    let template = r#"{ "age": {fuzz}, "note": "{fuzz} \" {fuzz}", "tags": [{fuzz}] }"#;
+
+    assert_eq!(render_body(template, BodyFormat::Json, &values("42")), r#"{ "age": 42, "note": "42 \" 42", "tags": [42] }"#);
+    assert_eq!(render_body(r#"{ "admin": {fuzz} }"#, BodyFormat::Json, &values(r#"{"$ne": null}"#)), r#"{ "admin": {"$ne": null} }"#);
+    assert_eq!(render_body(r#"{ "age": {fuzz} }"#, BodyFormat::Json, &values("1 OR 1=1")), r#"{ "age": "1 OR 1=1" }"#);
+  }
+
+  #[test]
+  fn test_render_form_body() {
+    let rendered = render_body("user={fuzz}&type=admin", BodyFormat::Form, &values("a&b=c d"));
+    assert_eq!(rendered, "user=a%26b%3Dc+d&type=admin");
+  }
+
+  #[test]
+  fn test_render_xml_body() {
+    let rendered = render_body("<user>{fuzz}</user>", BodyFormat::Xml, &values("<script>"));
+    assert_eq!(rendered, "<user>&lt;script&gt;</user>");
+  }
+
+  #[test]
+  fn test_render_raw_body() {
+    let rendered = render_body("user={fuzz}", BodyFormat::Raw, &values("' OR 1=1 --"));
+    assert_eq!(rendered, "user=' OR 1=1 --");
+  }
+
+  #[test]
+  fn test_render_body_with_named_placeholders() {
+    let values = HashMap::from([
+      ("fuzz1".to_string(), "a\"".to_string()),
+      ("fuzz2".to_string(), "b".to_string()),
+    ]);
+
+    let rendered = render_body(r#"{ "a": "{fuzz1}", "b": "{fuzz2}{fuzz1}" }"#, BodyFormat::Json, &values);
+    assert_eq!(rendered, r#"{ "a": "a\"", "b": "ba\"" }"#);
+  }
+}
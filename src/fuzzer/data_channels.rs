@@ -28,7 +28,9 @@ impl FuzzyResponseChannel {
 mod tests {
   use super::*;
   use tokio::runtime;
-  use crate::fuzzer::fuzzy::{FuzzedUrl, FuzzingResult};
+  use std::time::Duration;
+  use reqwest::header::HeaderMap;
+  use crate::fuzzer::fuzzy::{CapturedResponse, FuzzedUrl, FuzzingResult};
 
   #[tokio::test]
   async fn test_fuzzy_response_channel_creation() {
@@ -55,15 +57,21 @@ mod tests {
 
     let rt = runtime::Runtime::new().unwrap();
     rt.block_on(async {
-      sender.send(sample_fuzzing_result()).await.unwrap();
+      let sent = sample_fuzzing_result();
+      let fuzzed_url = sent.fuzzed_url().clone();
+      sender.send(sent).await.unwrap();
       let received = receiver.recv().await;
       assert!(received.is_some());
-      assert_eq!(received.unwrap(), sample_fuzzing_result()); // Adjust this if FuzzingResult doesn't implement PartialEq
+      assert_eq!(received.unwrap(), FuzzingResult::Success(fuzzed_url, sample_response())); // Adjust this if FuzzingResult doesn't implement PartialEq
     });
   }
 
   fn sample_fuzzing_result() -> FuzzingResult {
-    FuzzingResult::Success(FuzzedUrl::new("http://test.com/test".parse().unwrap(), "test".parse().unwrap(), "test".parse().unwrap()))
+    FuzzingResult::Success(FuzzedUrl::new("http://test.com/test".parse().unwrap(), "test".parse().unwrap(), "test".parse().unwrap()), sample_response())
+  }
+
+  fn sample_response() -> CapturedResponse {
+    CapturedResponse::new(200, HeaderMap::new(), "test".into(), Duration::from_millis(1))
   }
 }
 
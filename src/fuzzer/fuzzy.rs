@@ -1,26 +1,94 @@
+use std::collections::HashMap;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
-use reqwest::{Client, Error, Response};
+use futures::{stream, TryStreamExt};
+use serde::Serialize;
+use reqwest::{Client, Error, Method, RequestBuilder, Response};
 use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
-use tokio::sync::Semaphore;
+use tokio::sync::{OnceCell, Semaphore};
 use tokio::sync::mpsc::Sender;
-use url::{ParseError, Url};
-use uuid::uuid;
-use crate::domain::dictionary::FuzzyDictionary;
-use crate::domain::input::{FuzzyInput, HttpMethod, Path};
-
-const FUZZING_PLACEHOLDER: &str = "%7Bfuzz%7D";
+use tokio::task::JoinHandle;
+use url::Url;
+use uuid::Uuid;
+use crate::domain::dictionary::{FuzzyDictionary, Metadata};
+use crate::domain::input::{AttackStrategy, BodyMatch, FuzzyInput, HeaderMatch, HttpMethod, Path};
+use crate::domain::selector::Selection;
+use crate::domain::settings::{DEFAULT_ANOMALY_THRESHOLD, DEFAULT_DELAY_FACTOR};
+use crate::error::{FuzzyError, RequestError};
+use crate::fuzzer::attack::{combinations, AttackPoint, InjectedPayload};
+use crate::fuzzer::baseline::Baseline;
+use crate::fuzzer::matcher::ResponseMatcher;
+use crate::fuzzer::output::{truncate, RESPONSE_BODY_LIMIT};
+use crate::fuzzer::rate_limit::RateLimiter;
+use crate::fuzzer::signature::SignatureScanner;
+use crate::fuzzer::template::RequestTemplate;
+use crate::fuzzer::timing::{confirmation_payload, is_confirmed, is_delayed};
 
+/// Every method receives the path headers and body, an empty body is not sent.
 #[async_trait]
-trait HttpClient {
-  async fn get(&self, url: &str) -> Result<Response, Error>;
+pub trait HttpClient: Clone + Send + Sync + 'static {
+  async fn get(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn post(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn put(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn patch(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn delete(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn head(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
+  async fn options(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error>;
 }
 
 #[async_trait]
 impl HttpClient for Client {
-  async fn get(&self, url: &str) -> Result<Response, Error> {
-    self.get(url).send().await
+  async fn get(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::GET, url), headers, body).await
+  }
+
+  async fn post(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::POST, url), headers, body).await
+  }
+
+  async fn put(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::PUT, url), headers, body).await
+  }
+
+  async fn patch(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::PATCH, url), headers, body).await
+  }
+
+  async fn delete(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::DELETE, url), headers, body).await
+  }
+
+  async fn head(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::HEAD, url), headers, body).await
+  }
+
+  async fn options(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+    send(self.request(Method::OPTIONS, url), headers, body).await
+  }
+}
+
+async fn send(request: RequestBuilder, headers: HeaderMap, body: String) -> Result<Response, Error> {
+  let request = request.headers(headers);
+
+  if body.is_empty() {
+    request.send().await
+  } else {
+    request.body(body).send().await
+  }
+}
+
+/// Sends a request through the client method matching the http method.
+pub async fn send_request<C: HttpClient>(client: &C, method: &HttpMethod, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+  match method {
+    HttpMethod::GET => client.get(url, headers, body).await,
+    HttpMethod::POST => client.post(url, headers, body).await,
+    HttpMethod::PUT => client.put(url, headers, body).await,
+    HttpMethod::PATCH => client.patch(url, headers, body).await,
+    HttpMethod::DELETE => client.delete(url, headers, body).await,
+    HttpMethod::HEAD => client.head(url, headers, body).await,
+    HttpMethod::OPTIONS => client.options(url, headers, body).await,
   }
 }
 
@@ -28,7 +96,9 @@ impl HttpClient for Client {
 pub struct FuzzingFailure {
   network_error: Option<reqwest::Error>,
   status_code: Option<u16>,
-  response: Option<Response>,
+  response: Option<CapturedResponse>,
+  reasons: Vec<FailureReason>,
+  latency: Option<Duration>,
 }
 
 impl PartialEq for FuzzingFailure {
@@ -36,51 +106,264 @@ impl PartialEq for FuzzingFailure {
     let neq = self.network_error.is_some() == other.network_error.is_some();
     let seq = self.status_code == other.status_code;
     let req = self.response.is_some() == other.response.is_some();
+    let reasons_eq = self.reasons == other.reasons;
 
-    neq && seq && req
+    neq && seq && req && reasons_eq
   }
 }
 
 impl FuzzingFailure {
-  pub fn new(network_error: Option<reqwest::Error>, status_code: Option<u16>, response: Option<Response>) -> Self {
+  pub fn new(network_error: Option<reqwest::Error>, status_code: Option<u16>, response: Option<CapturedResponse>) -> Self {
     Self {
       network_error,
       status_code,
       response,
+      reasons: Vec::new(),
+      latency: None,
     }
   }
 
+  pub fn with_reasons(mut self, reasons: Vec<FailureReason>) -> Self {
+    self.reasons = reasons;
+    self
+  }
+
+  /// Sets how long a request without a response took to fail, e.g. to time out.
+  pub fn with_latency(mut self, latency: Duration) -> Self {
+    self.latency = Some(latency);
+    self
+  }
+
+  /// The latency of the response, or of the network error when there is none.
+  pub fn latency(&self) -> Option<Duration> {
+    self.response.as_ref().map(CapturedResponse::latency).or(self.latency)
+  }
+
+  pub fn reasons(&self) -> &Vec<FailureReason> {
+    &self.reasons
+  }
+
+  pub fn network_error(&self) -> &Option<reqwest::Error> {
+    &self.network_error
+  }
+
+  pub fn status_code(&self) -> &Option<u16> {
+    &self.status_code
+  }
+
+  pub fn response(&self) -> &Option<CapturedResponse> {
+    &self.response
+  }
+
   pub fn failure_to_string(&self, url: FuzzedUrl) -> String {
-    format!("id: {}, url: {}, status_code: {:?}, response: {:?}, network_error: {:?}", url.id(), url.url(), self.status_code, self.response, self.network_error)
+    let payloads: Vec<&String> = url.payloads().iter().map(|payload| payload.value()).collect();
+    let latency_ms = self.latency().map(|latency| latency.as_millis());
+    let metadata = url.metadata();
+    // the body is cut as in the json lines output
+    let response_headers = self.response.as_ref().map(|response| response.headers());
+    let (response_body, response_body_truncated) = match &self.response {
+      Some(response) => {
+        let (body, truncated) = truncate(response.body(), RESPONSE_BODY_LIMIT);
+        (Some(body), truncated)
+      }
+      None => (None, false),
+    };
+    format!("request_id: {}, id: {}, severity: {:?}, cves: {:?}, url: {}, payloads: {:?}, status_code: {:?}, latency_ms: {:?}, reasons: {:?}, response_headers: {:?}, response_body: {:?}, response_body_truncated: {}, network_error: {:?}", url.request_id(), url.id(), metadata.severity(), metadata.cves(), url.url(), payloads, self.status_code, latency_ms, self.reasons, response_headers, response_body, response_body_truncated, self.network_error)
+  }
+}
+
+/// Why a response was classified as a failure.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailureReason {
+  UnexpectedStatus { expected: u16, actual: u16 },
+  HeaderMismatch { name: String, expected: String, match_mode: HeaderMatch, actual: Option<String> },
+  BodyMismatch { match_mode: BodyMatch, detail: String },
+  /// The value fuzzed into a placeholder was echoed back, with the places it was found in.
+  Reflected { point: String, locations: Vec<String> },
+  /// The value fuzzed into a placeholder was expected to be echoed back but was not.
+  NotReflected { point: String },
+  /// The response stands out from the baseline responses of its path, with what deviated.
+  Anomaly { score: f64, deviations: Vec<String> },
+  /// The response took several times the baseline median, with the latency of the request resent with a longer
+  /// delay, which is missing when the payload has no delay to change.
+  Delayed { latency_ms: f64, baseline_ms: f64, confirmation_latency_ms: Option<f64> },
+  /// The body contains an error signature of the dictionaries, with the first text matching it.
+  Signature { id: String, matched: String },
+}
+
+/// Bytes of a response body kept for matching, longer bodies are cut so a huge response cannot exhaust memory.
+pub const CAPTURED_BODY_LIMIT: usize = 1024 * 1024;
+
+/// A response with its body read, so it can be matched and reported after the connection is released.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CapturedResponse {
+  status: u16,
+  headers: HeaderMap,
+  body: String,
+  latency: Duration,
+}
+
+impl CapturedResponse {
+  pub fn new(status: u16, headers: HeaderMap, body: String, latency: Duration) -> Self {
+    Self { status, headers, body, latency }
+  }
+
+  /// Reads the body up to `CAPTURED_BODY_LIMIT` bytes, the latency runs from `started` until these have been
+  /// received. The rest of a longer body is not read.
+  pub async fn capture(mut response: Response, started: Instant) -> Result<Self, Error> {
+    let status = response.status().as_u16();
+    let headers = response.headers().clone();
+    let mut body = Vec::new();
+    while let Some(chunk) = response.chunk().await? {
+      let remaining = CAPTURED_BODY_LIMIT - body.len();
+      body.extend_from_slice(&chunk[..chunk.len().min(remaining)]);
+      if body.len() == CAPTURED_BODY_LIMIT {
+        break;
+      }
+    }
+
+    Ok(Self::new(status, headers, String::from_utf8_lossy(&body).to_string(), started.elapsed()))
+  }
+
+  pub fn status(&self) -> u16 {
+    self.status
+  }
+
+  pub fn headers(&self) -> &HeaderMap {
+    &self.headers
+  }
+
+  pub fn body(&self) -> &String {
+    &self.body
+  }
+
+  pub fn latency(&self) -> Duration {
+    self.latency
   }
 }
 
 #[derive(PartialEq, Debug)]
 pub enum FuzzingResult {
-  Success(FuzzedUrl),
+  Success(FuzzedUrl, CapturedResponse),
   Failure(FuzzedUrl, FuzzingFailure),
+  /// The request could not be built as generated, e.g. a payload is not a valid header value.
+  BuildError(FuzzedUrl, RequestError),
+}
+
+impl FuzzingResult {
+  pub fn fuzzed_url(&self) -> &FuzzedUrl {
+    match self {
+      FuzzingResult::Success(fuzzed_url, _) => fuzzed_url,
+      FuzzingResult::Failure(fuzzed_url, _) => fuzzed_url,
+      FuzzingResult::BuildError(fuzzed_url, _) => fuzzed_url,
+    }
+  }
+
+  /// Adds a reason found after classifying the response, turning a success into a failure.
+  pub fn with_reason(self, reason: FailureReason) -> Self {
+    match self {
+      FuzzingResult::Success(fuzzed_url, response) => {
+        let failure = FuzzingFailure::new(None, Some(response.status()), Some(response)).with_reasons(vec![reason]);
+        FuzzingResult::Failure(fuzzed_url, failure)
+      }
+      FuzzingResult::Failure(fuzzed_url, mut failure) => {
+        failure.reasons.push(reason);
+        FuzzingResult::Failure(fuzzed_url, failure)
+      }
+      result => result,
+    }
+  }
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct FuzzedUrl {
+  request_id: Uuid,
+  method: HttpMethod,
   url: String,
   description: String,
   id: String,
+  metadata: Metadata,
+  payloads: Vec<InjectedPayload>,
+  headers: Vec<(String, String)>,
+  body: String,
 }
 
 impl FuzzedUrl {
+  /// Every fuzzed url gets a new request id, generated before the request is sent.
   pub fn new(url: String, description: String, id: String) -> Self {
     Self {
+      request_id: Uuid::new_v4(),
+      method: HttpMethod::GET,
       url,
       description,
       id,
+      metadata: Metadata::default(),
+      payloads: Vec::new(),
+      headers: Vec::new(),
+      body: String::new(),
     }
   }
 
+  /// Sets the injected payloads, taking the id, description and metadata from the dictionaries they came from.
+  pub fn with_payloads(mut self, payloads: Vec<InjectedPayload>) -> Self {
+    let mut ids: Vec<&String> = Vec::new();
+    let mut descriptions: Vec<&String> = Vec::new();
+    let mut metadata = Metadata::default();
+    for payload in &payloads {
+      if !ids.contains(&payload.id()) {
+        ids.push(payload.id());
+        descriptions.push(payload.description());
+        metadata.merge(payload.metadata());
+      }
+    }
+
+    self.id = ids.iter().map(|id| id.as_str()).collect::<Vec<_>>().join("+");
+    self.description = descriptions.iter().map(|description| description.as_str()).collect::<Vec<_>>().join("; ");
+    self.metadata = metadata;
+    self.payloads = payloads;
+    self
+  }
+
+  pub fn with_method(mut self, method: HttpMethod) -> Self {
+    self.method = method;
+    self
+  }
+
+  pub fn with_headers(mut self, headers: Vec<(String, String)>) -> Self {
+    self.headers = headers;
+    self
+  }
+
+  pub fn with_body(mut self, body: String) -> Self {
+    self.body = body;
+    self
+  }
+
+  pub fn request_id(&self) -> &Uuid {
+    &self.request_id
+  }
+
+  pub fn method(&self) -> &HttpMethod {
+    &self.method
+  }
+
+  pub fn payloads(&self) -> &Vec<InjectedPayload> {
+    &self.payloads
+  }
+
+  pub fn headers(&self) -> &Vec<(String, String)> {
+    &self.headers
+  }
+
   pub fn url(&self) -> &String {
     &self.url
   }
 
+  pub fn body(&self) -> &String {
+    &self.body
+  }
+
   pub fn description(&self) -> &String {
     &self.description
   }
@@ -88,153 +371,1205 @@ impl FuzzedUrl {
   pub fn id(&self) -> &String {
     &self.id
   }
+
+  pub fn metadata(&self) -> &Metadata {
+    &self.metadata
+  }
+}
+
+/// What the requests of a path share, built once before fuzzing it.
+struct PathContext {
+  template: RequestTemplate,
+  defaults: HashMap<String, String>,
+  request_id_header: Option<String>,
+  matcher: ResponseMatcher,
+  baseline: Option<Baseline>,
+  /// Latency of a request with the defaults, timed once the first payload with a delay is sent on a path without a
+  /// baseline.
+  latency_sample: OnceCell<Option<Duration>>,
+  signatures: Arc<SignatureScanner>,
 }
 
 #[derive(Clone)]
-pub struct Fuzzer {
-  client: Client,
+pub struct Fuzzer<C: HttpClient = Client> {
+  client: C,
   semaphore: Arc<Semaphore>,
+  /// Requests of a path in flight at once, the semaphore bounds them over every path.
+  concurrency: usize,
+  rate_limiter: Arc<RateLimiter>,
+  selection: Selection,
   tx: Sender<FuzzingResult>,
+  baseline_requests: usize,
+  anomaly_threshold: f64,
+  delay_factor: f64,
 }
 
 impl Fuzzer {
   pub fn new(num_of_concurrent_requests: usize, tx: Sender<FuzzingResult>) -> Self {
+    Fuzzer::with_client(Client::new(), num_of_concurrent_requests, tx)
+  }
+}
+
+impl<C: HttpClient> Fuzzer<C> {
+  pub fn with_client(client: C, num_of_concurrent_requests: usize, tx: Sender<FuzzingResult>) -> Self {
     Fuzzer {
-      client: Client::new(),
+      client,
       semaphore: Arc::new(Semaphore::new(num_of_concurrent_requests)),
+      concurrency: num_of_concurrent_requests.max(1),
+      rate_limiter: Arc::new(RateLimiter::default()),
+      selection: Selection::default(),
       tx,
+      baseline_requests: 0,
+      anomaly_threshold: DEFAULT_ANOMALY_THRESHOLD,
+      delay_factor: DEFAULT_DELAY_FACTOR,
     }
   }
 
-  fn generate_url(&self, input_data: &FuzzyInput, path: &Path) -> Result<Url, ParseError> {
+  /// Sends `requests` benign requests per path before fuzzing it, and reports fuzzed responses whose deviations from
+  /// them add up to `threshold` instead of checking `expected_status`.
+  pub fn with_baseline(mut self, requests: usize, threshold: f64) -> Self {
+    self.baseline_requests = requests;
+    self.anomaly_threshold = threshold;
+    self
+  }
+
+  /// Only fuzzes with the selected dictionaries, unless a path or fuzz point includes its own.
+  pub fn with_selection(mut self, selection: Selection) -> Self {
+    self.selection = selection;
+    self
+  }
+
+  /// Paces every request, including baseline and confirmation requests, on top of the concurrency limit.
+  pub fn with_rate_limiter(mut self, rate_limiter: RateLimiter) -> Self {
+    self.rate_limiter = Arc::new(rate_limiter);
+    self
+  }
+
+  /// Flags responses taking `factor` times the baseline median latency, 0 turns this off. Paths without a baseline
+  /// are compared with a request with the defaults.
+  pub fn with_delay_factor(mut self, factor: f64) -> Self {
+    self.delay_factor = factor;
+    self
+  }
+
+  fn generate_url(&self, input_data: &FuzzyInput, path: &Path) -> Result<Url, FuzzyError> {
     path.to_url(input_data.host(), input_data.base_path())
-      .map_err(|err| {
-        log::error!("failed to parse url: {}", err);
-        err
+      .map_err(|source| FuzzyError::InvalidUrl { endpoint: path.endpoint().clone(), source })
+  }
+
+  /// The placeholders of a path, each with the payloads of the dictionaries selected for it.
+  fn attack_points<'a>(&self, template: &RequestTemplate, path: &Path, dict: &'a FuzzyDictionary) -> Vec<AttackPoint<'a>> {
+    let selection = self.selection.narrow(path.include(), path.exclude());
+    let points: Vec<AttackPoint> = template.placeholders()
+      .iter()
+      .map(|name| AttackPoint::new(name, path.fuzz_point(name), dict, &selection))
+      .collect();
+
+    if points.is_empty() {
+      log::warn!("no placeholders to fuzz in {}", path.endpoint());
+    }
+
+    points
+  }
+
+  /// Renders a fuzzed request per combination of the attack, lazily so only the requests in flight are held in memory.
+  fn generate_fuzzed_urls<'a>(&self, template: &'a RequestTemplate, points: &'a [AttackPoint<'a>], attack: &AttackStrategy, request_id_header: &'a Option<String>) -> impl Iterator<Item = FuzzedUrl> + Send + 'a {
+    let defaults: HashMap<String, String> = points
+      .iter()
+      .map(|point| (point.name().clone(), point.default().clone()))
+      .collect();
+
+    combinations(attack, points)
+      .map(move |payloads| {
+        let mut values = defaults.clone();
+        for payload in &payloads {
+          values.insert(payload.point().clone(), payload.value().clone());
+        }
+
+        with_request_id(template.render(&values).with_payloads(payloads), request_id_header)
+      })
+  }
+
+  /// Renders the baseline requests of a path, the first with every placeholder at its default value and the others
+  /// with random alphanumeric values, which most applications reject the same way as an invalid payload.
+  fn generate_baseline_urls(&self, url: &Url, path: &Path, request_id_header: &Option<String>) -> Vec<FuzzedUrl> {
+    let template = RequestTemplate::new(url, path);
+    let names = template.placeholders();
+
+    (0..self.baseline_requests)
+      .map(|index| {
+        let values: HashMap<String, String> = names
+          .iter()
+          .map(|name| {
+            let value = match index {
+              0 => path.fuzz_point(name).map(|point| point.default().clone()).unwrap_or_default(),
+              _ => Uuid::new_v4().simple().to_string()[..12].to_string(),
+            };
+            (name.clone(), value)
+          })
+          .collect();
+
+        with_request_id(template.render(&values), request_id_header)
       })
+      .collect()
   }
 
-  fn generate_fuzzed_urls(&self, url: &Url, dict: &FuzzyDictionary) -> Vec<FuzzedUrl> {
-    let mut fuzzed_urls = Vec::new();
-    for item in dict.data() {
-      for fuzz_param in item.values() {
-        let fuzzed_url = url.to_string().replace(FUZZING_PLACEHOLDER, fuzz_param);
-        fuzzed_urls.push(FuzzedUrl {
-          url: fuzzed_url,
-          description: item.description().to_string(),
-          id: item.id().to_string(),
-        });
+  /// Sends the baseline requests of a path one at a time, `None` when none of them got a response. Signatures found in
+  /// the baseline responses are not reported for the path, since the benign requests trigger them too.
+  async fn measure_baseline(&self, url: &Url, path: &Path, request_id_header: &Option<String>, signatures: &SignatureScanner) -> Option<Baseline> {
+    if self.baseline_requests == 0 {
+      return None;
+    }
+
+    let mut responses = Vec::new();
+    for fuzzed_url in self.generate_baseline_urls(url, path, request_id_header) {
+      let response = match self.build_headers(&fuzzed_url) {
+        Ok(headers) => self.make_request(&fuzzed_url, headers).await.map_err(|(err, _)| err.to_string()),
+        Err(err) => Err(err.to_string()),
+      };
+
+      match response {
+        Ok(response) => responses.push(response),
+        Err(err) => log::warn!("baseline request to {} failed: {}", fuzzed_url.url(), err),
       }
     }
 
-    fuzzed_urls
+    let known_signatures = responses.iter().flat_map(|response| signatures.matching_ids(response.body())).collect();
+    let baseline = Baseline::new(&responses, self.anomaly_threshold).map(|baseline| baseline.with_signatures(known_signatures));
+    match &baseline {
+      Some(_) => log::debug!("measured a baseline of {} responses for {}", responses.len(), path.endpoint()),
+      None => log::warn!("no baseline for {}, checking expected_status instead", path.endpoint()),
+    }
+
+    baseline
   }
 
-  async fn make_request(&self, fuzzed_url: &FuzzedUrl, path: &Path) -> Result<Response, reqwest::Error> {
+  /// Builds the header map for a request, returning the error when a name or value cannot be sent.
+  fn build_headers(&self, fuzzed_url: &FuzzedUrl) -> Result<HeaderMap, RequestError> {
+    header_map(fuzzed_url.headers().iter().map(|(name, value)| (name, value)))
+  }
+
+  /// Sends a request, an error comes with how long the request took to fail.
+  async fn make_request(&self, fuzzed_url: &FuzzedUrl, headers: HeaderMap) -> Result<CapturedResponse, (reqwest::Error, Duration)> {
+    // paced requests wait before taking a concurrency slot, so they do not hold one while sleeping
+    self.rate_limiter.wait(fuzzed_url.url()).await;
     let _permit = self.semaphore.acquire().await;
-    let mut headers = HeaderMap::new();
 
-    for header in path.headers() {
-      headers.insert(
-        HeaderName::from_str(header.name().as_str()).expect("invalid header name provided"),
-        HeaderValue::from_str(header.value().as_ref().expect("missing header value").as_str()).expect("invalid header value provided"),
-      );
+    log::trace!("making {:?} request: {}", fuzzed_url.method(), fuzzed_url.url);
+
+    let url = fuzzed_url.url();
+    let body = fuzzed_url.body().clone();
+    let started = Instant::now();
+    let response = match send_request(&self.client, fuzzed_url.method(), url, headers, body).await {
+      Ok(response) => response,
+      Err(err) => return Err((err, started.elapsed())),
+    };
+
+    CapturedResponse::capture(response, started).await.map_err(|err| (err, started.elapsed()))
+  }
+
+  /// Flags a response taking several times the baseline median once it is confirmed by another request. A payload
+  /// with a delay, e.g. `SLEEP(5)`, is sent again with a longer delay and flagged when the latency grows along with
+  /// it, any other payload is sent again as it is and flagged when it is slow again, so a single slow response such as
+  /// a garbage collection pause is not reported.
+  async fn check_delay(&self, fuzzed_url: &FuzzedUrl, latency: Duration, median: Duration, context: &PathContext) -> Option<FailureReason> {
+    if !is_delayed(latency, median, self.delay_factor) {
+      return None;
     }
 
-    log::trace!("making request: {}", fuzzed_url.url);
+    let mut values = context.defaults.clone();
+    let mut has_delay = false;
+    for payload in fuzzed_url.payloads() {
+      let value = match confirmation_payload(payload.value()) {
+        Some(value) => {
+          has_delay = true;
+          value
+        }
+        None => payload.value().clone(),
+      };
+      values.insert(payload.point().clone(), value);
+    }
 
-    self.client.get(fuzzed_url.url())
-      .headers(headers)
-      .send()
-      .await
+    let confirmation = with_request_id(context.template.render(&values), &context.request_id_header);
+    let headers = self.build_headers(&confirmation).ok()?;
+    let confirmation_latency = match self.make_request(&confirmation, headers).await {
+      Ok(response) => response.latency(),
+      Err((_, latency)) => latency,
+    };
+
+    let confirmed = if has_delay {
+      is_confirmed(latency, confirmation_latency, median)
+    } else {
+      is_delayed(confirmation_latency, median, self.delay_factor)
+    };
+    if !confirmed {
+      log::debug!("delay of {} not confirmed, took {:?} then {:?}", fuzzed_url.url(), latency, confirmation_latency);
+      return None;
+    }
+
+    Some(FailureReason::Delayed {
+      latency_ms: milliseconds(latency),
+      baseline_ms: milliseconds(median),
+      confirmation_latency_ms: Some(milliseconds(confirmation_latency)),
+    })
   }
 
-  async fn log_metrics(&self, response: Result<Response, reqwest::Error>, fuzzed_url: &FuzzedUrl, path: &Path) {
-    let id = uuid::Uuid::new_v4();
+  /// Classifies a response as a success or a failure against the expectations of its path, the status is checked
+  /// against the baseline instead when there is one. Error signatures are reported whatever the status.
+  fn to_result(&self, response: Result<CapturedResponse, (reqwest::Error, Duration)>, fuzzed_url: FuzzedUrl, context: &PathContext) -> FuzzingResult {
+    let id = fuzzed_url.request_id();
+    let matcher = &context.matcher;
     match response {
       Ok(success) => {
-        // let mut metrics = self.metrics.write().await;
-        if success.status().as_u16() != path.expected_status().clone() {
-          log::info!("Failure!!!! {}", id);
-          let fuzzing_failure = FuzzingFailure::new(Option::None, Option::Some(success.status().as_u16()), Option::Some(success));
-          self.tx.send(FuzzingResult::Failure(fuzzed_url.clone(), fuzzing_failure)).await.unwrap();
-          // log::info!("\t➡️ received a non expected status code: {}\ndescription: ({}) {}\nurl: {}", success.status().as_u16(), fuzzed_url.id, fuzzed_url.description, fuzzed_url.url);
-          // metrics.failed_requests += 1;
+        let (mut reasons, known_signatures): (Vec<FailureReason>, &[String]) = match &context.baseline {
+          Some(baseline) => (baseline.check(&success).into_iter().collect(), baseline.signatures()),
+          None => (matcher.check_status(success.status()).into_iter().collect(), &[]),
+        };
+        reasons.extend(matcher.check_headers(success.headers()));
+        reasons.extend(matcher.check_body(success.body()));
+        reasons.extend(matcher.check_reflection(fuzzed_url.payloads(), success.headers(), success.body()));
+        reasons.extend(context.signatures.check(success.body(), known_signatures));
+
+        if !reasons.is_empty() {
+          log::debug!("request {} to {} failed: {:?}", id, fuzzed_url.url(), reasons);
+          let fuzzing_failure = FuzzingFailure::new(Option::None, Option::Some(success.status()), Option::Some(success))
+            .with_reasons(reasons);
+          FuzzingResult::Failure(fuzzed_url, fuzzing_failure)
         } else {
-          self.tx.send(FuzzingResult::Success(fuzzed_url.clone())).await.unwrap();
-          // metrics.successful_requests += 1;
+          FuzzingResult::Success(fuzzed_url, success)
         }
-        // metrics.total_requests += 1;
       }
-      Err(err) => {
-        log::info!("Another Failure!!!! {}", id);
-        let fuzzing_failure = FuzzingFailure::new(Option::Some(err), Option::None, Option::None);
-        self.tx.send(FuzzingResult::Failure(fuzzed_url.clone(),fuzzing_failure)).await.unwrap();
-        // log::info!("\t➡️ received an error whilst fuzzing: {}\ndescription: ({}) {}\nurl: {}", err, fuzzed_url.id, fuzzed_url.description, fuzzed_url.url);
-        // let mut metrics = self.metrics.write().await;
-        // metrics.failed_requests += 1;
-        // metrics.total_requests += 1;
+      Err((err, latency)) => {
+        log::debug!("request {} to {} got no response: {}", id, fuzzed_url.url(), err);
+        let fuzzing_failure = FuzzingFailure::new(Option::Some(err), Option::None, Option::None).with_latency(latency);
+        FuzzingResult::Failure(fuzzed_url, fuzzing_failure)
       }
     }
   }
 
-  pub async fn fuzz(&self, input_data: &FuzzyInput, dict: &FuzzyDictionary) {
+  /// The latency delays are measured against, the baseline median or else a single request with the defaults, which
+  /// is only sent when delay detection is on and a payload has a delay to detect.
+  async fn median_latency(&self, fuzzed_url: &FuzzedUrl, context: &PathContext) -> Option<Duration> {
+    if let Some(baseline) = &context.baseline {
+      return Some(baseline.median_latency());
+    }
+
+    if self.delay_factor <= 0.0 || !fuzzed_url.payloads().iter().any(|payload| confirmation_payload(payload.value()).is_some()) {
+      return None;
+    }
+
+    *context.latency_sample.get_or_init(|| async {
+      let sample = with_request_id(context.template.render(&context.defaults), &context.request_id_header);
+      let headers = self.build_headers(&sample).ok()?;
+      match self.make_request(&sample, headers).await {
+        Ok(response) => {
+          log::debug!("timed {} at {:?} to detect delays without a baseline", sample.url(), response.latency());
+          Some(response.latency())
+        }
+        Err((err, _)) => {
+          log::warn!("could not time {} to detect delays: {}", sample.url(), err);
+          None
+        }
+      }
+    }).await
+  }
+
+  async fn fuzz_request(&self, fuzzed_url: FuzzedUrl, context: &PathContext) -> Result<(), FuzzyError> {
+    let result = match self.build_headers(&fuzzed_url) {
+      Ok(headers) => {
+        let response = self.make_request(&fuzzed_url, headers).await;
+        let delay = match &response {
+          Ok(response) => match self.median_latency(&fuzzed_url, context).await {
+            Some(median) => self.check_delay(&fuzzed_url, response.latency(), median, context).await,
+            None => None,
+          },
+          Err(_) => None,
+        };
+
+        let result = self.to_result(response, fuzzed_url, context);
+        match delay {
+          Some(reason) => result.with_reason(reason),
+          None => result,
+        }
+      }
+      Err(err) => {
+        log::debug!("could not build request {}: {}", fuzzed_url.url(), err);
+        FuzzingResult::BuildError(fuzzed_url, err)
+      }
+    };
+
+    self.tx.send(result).await?;
+    Ok(())
+  }
+
+  async fn fuzz_path(&self, input_data: &FuzzyInput, path: &Path, dict: &FuzzyDictionary, signatures: Arc<SignatureScanner>) -> Result<(), FuzzyError> {
+    let matcher = ResponseMatcher::new(path)
+      .map_err(|source| FuzzyError::InvalidExpectations { endpoint: path.endpoint().clone(), source })?;
+
+    let url = self.generate_url(input_data, path)?;
+    let template = RequestTemplate::new(&url, path);
+    let defaults = template.placeholders()
+      .into_iter()
+      .map(|name| {
+        let default = path.fuzz_point(&name).map(|point| point.default().clone()).unwrap_or_default();
+        (name, default)
+      })
+      .collect();
+    let context = PathContext {
+      template,
+      defaults,
+      request_id_header: input_data.request_id_header().clone(),
+      matcher,
+      baseline: self.measure_baseline(&url, path, input_data.request_id_header(), &signatures).await,
+      latency_sample: OnceCell::new(),
+      signatures,
+    };
+
+    // requests are generated as the previous ones complete, which also waits on a full results channel
+    let points = self.attack_points(&context.template, path, dict);
+    let fuzzed_urls = self.generate_fuzzed_urls(&context.template, &points, path.attack(), &context.request_id_header);
+    stream::iter(fuzzed_urls.map(Ok))
+      .try_for_each_concurrent(self.concurrency, |fuzzed_url| self.fuzz_request(fuzzed_url, &context))
+      .await
+  }
+
+  /// Fuzzes every path, a path that fails does not stop the others and the first error is returned once all finish.
+  pub async fn fuzz(&self, input_data: &FuzzyInput, dict: Arc<FuzzyDictionary>) -> Result<(), FuzzyError> {
+    let signatures = Arc::new(SignatureScanner::new(dict.signatures())?);
+    // cloned once and shared by every path, like the dictionary which can hold millions of values
+    let input_data = Arc::new(input_data.clone());
     // vec to hold JoinHandle of each spawned task
     let mut path_handles = Vec::new();
 
-    for path in input_data.paths().clone() {
+    for index in 0..input_data.paths().len() {
       let input_data_clone = input_data.clone();
       let dict_clone = dict.clone();
+      let signatures_clone = signatures.clone();
       let self_clone = self.clone();
 
-      let handle = tokio::spawn(async move {
-        if let Ok(url) = self_clone.generate_url(&input_data_clone, &path) {
-          let fuzzed_urls = self_clone.generate_fuzzed_urls(&url, &dict_clone);
-          let mut request_handles = Vec::new();
-
-          for fuzzed_url in fuzzed_urls {
-            if path.method() == &HttpMethod::GET {
-              let fuzzed_url_clone = fuzzed_url.clone();
-              let path_clone = path.clone();
-              let self_clone_inner = self_clone.clone();
-
-              let request_handle = tokio::spawn(async move {
-                let response = self_clone_inner.make_request(&fuzzed_url_clone, &path_clone).await;
-                self_clone_inner.log_metrics( response, &fuzzed_url_clone, &path_clone).await;
-              });
-
-              request_handles.push(request_handle);
-            }
-          }
-
-          for handle in request_handles {
-            handle.await.unwrap();
-          }
-        }
-      });
+      path_handles.push(tokio::spawn(async move {
+        let path = &input_data_clone.paths()[index];
+        self_clone.fuzz_path(&input_data_clone, path, &dict_clone, signatures_clone).await
+      }));
+    }
 
-      path_handles.push(handle);
+    join_all(path_handles).await
+  }
+}
+
+fn milliseconds(duration: Duration) -> f64 {
+  duration.as_secs_f64() * 1000.0
+}
+
+/// Appends the request id header when the input sets one.
+fn with_request_id(fuzzed_url: FuzzedUrl, request_id_header: &Option<String>) -> FuzzedUrl {
+  match request_id_header {
+    Some(header) => {
+      let mut headers = fuzzed_url.headers().clone();
+      headers.push((header.clone(), fuzzed_url.request_id().to_string()));
+      fuzzed_url.with_headers(headers)
     }
+    None => fuzzed_url,
+  }
+}
+
+/// Builds a header map, returning the error for the first name or value that cannot be sent.
+pub fn header_map<'a, I: IntoIterator<Item = (&'a String, &'a String)>>(headers: I) -> Result<HeaderMap, RequestError> {
+  let mut header_map = HeaderMap::new();
 
-    for handle in path_handles {
-      handle.await.unwrap();
+  for (name, value) in headers {
+    let header_name = HeaderName::from_str(name)
+      .map_err(|_| RequestError::InvalidHeaderName { name: name.clone() })?;
+    let header_value = HeaderValue::from_bytes(value.as_bytes())
+      .map_err(|_| RequestError::InvalidHeaderValue { name: name.clone(), value: value.clone() })?;
+    header_map.append(header_name, header_value);
+  }
+
+  Ok(header_map)
+}
+
+/// Waits for every task, returning the first error after logging any others.
+async fn join_all(handles: Vec<JoinHandle<Result<(), FuzzyError>>>) -> Result<(), FuzzyError> {
+  let mut first_error = None;
+
+  for handle in handles {
+    if let Err(err) = handle.await.map_err(FuzzyError::from).and_then(|result| result) {
+      match first_error {
+        None => first_error = Some(err),
+        Some(_) => log::error!("{}", err),
+      }
     }
   }
+
+  first_error.map_or(Ok(()), Err)
 }
 
 #[cfg(test)]
 mod tests {
+  use std::sync::Mutex;
   use hyper::http;
-  use crate::domain::input::QueryParameter;
+  use crate::domain::dictionary::{FuzzyData, Severity};
+  use crate::domain::input::{AttackStrategy, ExpectedHeader, FuzzPoint, HeaderParameter, QueryParameter, ReflectionMode};
+  use crate::domain::settings::Settings;
+  use crate::domain::transform::Transform;
   use super::*;
 
-  struct MockClient;
+  #[derive(Clone, Debug)]
+  struct MockRequest {
+    method: &'static str,
+    url: String,
+    headers: HeaderMap,
+    body: String,
+  }
+
+  #[derive(Clone, Default)]
+  struct MockClient {
+    requests: Arc<Mutex<Vec<MockRequest>>>,
+    response_body: &'static str,
+    /// Status and body answered instead for urls containing the key.
+    overrides: Vec<(&'static str, u16, &'static str)>,
+    /// How long to wait before answering urls containing the key.
+    delays: Vec<(&'static str, Duration)>,
+    /// How long to wait before answering the first request to a url containing the key.
+    first_delays: Vec<(&'static str, Duration)>,
+  }
+
+  impl MockClient {
+    fn with_response_body(response_body: &'static str) -> Self {
+      Self { response_body, ..Default::default() }
+    }
+
+    fn with_override(mut self, url_part: &'static str, status: u16, body: &'static str) -> Self {
+      self.overrides.push((url_part, status, body));
+      self
+    }
+
+    fn with_delay(mut self, url_part: &'static str, delay: Duration) -> Self {
+      self.delays.push((url_part, delay));
+      self
+    }
+
+    fn with_first_delay(mut self, url_part: &'static str, delay: Duration) -> Self {
+      self.first_delays.push((url_part, delay));
+      self
+    }
+
+    async fn record(&self, method: &'static str, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      let earlier: Vec<String> = self.requests.lock().unwrap().iter().map(|request| request.url.clone()).collect();
+      self.requests.lock().unwrap().push(MockRequest { method, url: url.to_string(), headers, body });
+
+      if let Some((_, delay)) = self.delays.iter().find(|(url_part, _)| url.contains(url_part)) {
+        tokio::time::sleep(*delay).await;
+      }
+      let first_delay = self.first_delays.iter().find(|(url_part, _)| url.contains(url_part) && !earlier.iter().any(|earlier| earlier.contains(url_part)));
+      if let Some((_, delay)) = first_delay {
+        tokio::time::sleep(*delay).await;
+      }
+
+      let response = match self.overrides.iter().find(|(url_part, _, _)| url.contains(url_part)) {
+        Some((_, status, body)) => http::response::Builder::new().status(*status).body(*body).unwrap(),
+        None => http::response::Response::new(self.response_body),
+      };
+      Ok(Response::from(response))
+    }
+
+    fn requests(&self) -> Vec<MockRequest> {
+      self.requests.lock().unwrap().clone()
+    }
+  }
 
   #[async_trait]
   impl HttpClient for MockClient {
-    async fn get(&self, _url: &str) -> Result<Response, Error> {
-      Ok(Response::from(http::response::Response::new("test")))
+    async fn get(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("GET", url, headers, body).await
+    }
+
+    async fn post(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("POST", url, headers, body).await
+    }
+
+    async fn put(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("PUT", url, headers, body).await
+    }
+
+    async fn patch(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("PATCH", url, headers, body).await
+    }
+
+    async fn delete(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("DELETE", url, headers, body).await
+    }
+
+    async fn head(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("HEAD", url, headers, body).await
+    }
+
+    async fn options(&self, url: &str, headers: HeaderMap, body: String) -> Result<Response, Error> {
+      self.record("OPTIONS", url, headers, body).await
+    }
+  }
+
+  fn sample_dictionary() -> FuzzyDictionary {
+    serde_yaml::from_str(r#"
+      data:
+        - id: "1"
+          description: "test description"
+          values: ["value1"]
+      "#).unwrap()
+  }
+
+  fn sample_path(method: HttpMethod) -> Path {
+    Path::new(
+      "/test".into(),
+      method,
+      200,
+      vec![],
+      "".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![HeaderParameter::new("Accept".into(), Some("application/json".into()), false)],
+      "{}".into()
+    )
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_dispatches_every_method() {
+    let methods = vec![
+      (HttpMethod::GET, "GET"),
+      (HttpMethod::POST, "POST"),
+      (HttpMethod::PUT, "PUT"),
+      (HttpMethod::PATCH, "PATCH"),
+      (HttpMethod::DELETE, "DELETE"),
+      (HttpMethod::HEAD, "HEAD"),
+      (HttpMethod::OPTIONS, "OPTIONS"),
+    ];
+
+    for (method, expected_method) in methods {
+      let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+      let client = MockClient::default();
+      let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+      let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sample_path(method)]);
+
+      fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+      let requests = client.requests();
+      assert_eq!(requests.len(), 1);
+      assert_eq!(requests[0].method, expected_method);
+      assert_eq!(requests[0].url, "https://example.com/api/test?q=value1");
+      assert_eq!(requests[0].headers.get("Accept").unwrap(), "application/json");
+      assert_eq!(requests[0].body, "{}");
+      assert!(rx.recv().await.is_some());
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_renders_body_for_content_type() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+    let path = Path::new(
+      "/test".into(),
+      HttpMethod::POST,
+      200,
+      vec![],
+      "".into(),
+      vec![],
+      vec![HeaderParameter::new("Content-Type".into(), Some("application/json".into()), false)],
+      r#"{ "user": "{fuzz}" }"#.into()
+    );
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: "1"
+          description: "quotes"
+          values: ["\"\\"]
+      "#).unwrap();
+
+    fuzzer.fuzz(&input_data, Arc::new(dict)).await.unwrap();
+
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].body, r#"{ "user": "\"\\" }"#);
+  }
+
+  fn sample_header_path(headers: Vec<HeaderParameter>) -> Path {
+    Path::new("/test".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], headers, "".into())
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_header_values() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+    let path = sample_header_path(vec![
+      HeaderParameter::new("X-User".into(), None, true),
+      HeaderParameter::new("Authorization".into(), Some("Bearer {fuzz}".into()), true),
+      HeaderParameter::new("Accept".into(), Some("{fuzz}".into()), false),
+    ]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers.get("X-User").unwrap(), "value1");
+    assert_eq!(requests[0].headers.get("Authorization").unwrap(), "Bearer value1");
+    assert_eq!(requests[0].headers.get("Accept").unwrap(), "{fuzz}");
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_unbuildable_requests() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+    let path = sample_header_path(vec![HeaderParameter::new("X-User".into(), None, true)]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: "1"
+          description: "header injection"
+          values: ["a\r\nSet-Cookie: x=y", "naïve"]
+      "#).unwrap();
+
+    fuzzer.fuzz(&input_data, Arc::new(dict)).await.unwrap();
+
+    let requests = client.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].headers.get("X-User").unwrap().as_bytes(), "naïve".as_bytes());
+
+    let mut build_errors = 0;
+    while let Ok(result) = rx.try_recv() {
+      if let FuzzingResult::BuildError(fuzzed_url, err) = result {
+        assert_eq!(fuzzed_url.headers()[0].1, "a\r\nSet-Cookie: x=y");
+        assert_eq!(err, RequestError::InvalidHeaderValue { name: "X-User".into(), value: "a\r\nSet-Cookie: x=y".into() });
+        build_errors += 1;
+      }
+    }
+    assert_eq!(build_errors, 1);
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_returns_error_when_channel_is_closed() {
+    let (tx, rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    drop(rx);
+    let fuzzer = Fuzzer::with_client(MockClient::default(), 1, tx);
+    let path = sample_header_path(vec![HeaderParameter::new("X-User".into(), None, true)]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    let result = fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await;
+
+    assert!(matches!(result, Err(FuzzyError::ChannelClosed)));
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_continues_after_invalid_path() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+    let invalid = Path::new("/invalid".into(), HttpMethod::GET, 200, vec![], "(".into(), vec![QueryParameter::new("q".into(), true, None)], vec![], "".into())
+      .with_body_match(BodyMatch::Regex);
+    let valid = sample_header_path(vec![HeaderParameter::new("X-User".into(), None, true)]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![invalid, valid]);
+
+    let result = fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await;
+
+    assert!(matches!(result, Err(FuzzyError::InvalidExpectations { .. })));
+    assert_eq!(client.requests().len(), 1);
+    assert!(rx.try_recv().is_ok());
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_header_mismatches() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let fuzzer = Fuzzer::with_client(MockClient::default(), 1, tx);
+    let path = Path::new(
+      "/test".into(),
+      HttpMethod::GET,
+      200,
+      vec![ExpectedHeader::new("Content-Type".into(), "application/json".into(), HeaderMatch::Prefix)],
+      "".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![],
+      "".into()
+    );
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => assert_eq!(failure.reasons(), &vec![FailureReason::HeaderMismatch {
+        name: "Content-Type".into(),
+        expected: "application/json".into(),
+        match_mode: HeaderMatch::Prefix,
+        actual: None,
+      }]),
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_body_mismatches() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let fuzzer = Fuzzer::with_client(MockClient::with_response_body("java.lang.NullPointerException\n\tat Controller.java:42"), 1, tx);
+    let path = Path::new(
+      "/test".into(),
+      HttpMethod::GET,
+      200,
+      vec![],
+      "{ \"hello\": \"*\" }".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![],
+      "".into()
+    ).with_body_match(BodyMatch::Json);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => {
+        assert_eq!(failure.reasons().len(), 1);
+        assert!(matches!(failure.reasons()[0], FailureReason::BodyMismatch { match_mode: BodyMatch::Json, .. }));
+      }
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_reflected_payloads() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let fuzzer = Fuzzer::with_client(MockClient::with_response_body("<p>results for value1</p>"), 1, tx);
+    let path = Path::new(
+      "/search".into(),
+      HttpMethod::GET,
+      200,
+      vec![],
+      "".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![],
+      "".into()
+    ).with_reflection(ReflectionMode::Reflected);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(fuzzed_url, failure) => {
+        assert_eq!(fuzzed_url.payloads()[0].value(), "value1");
+        assert_eq!(failure.reasons(), &vec![FailureReason::Reflected { point: "fuzz".into(), locations: vec!["body".into()] }]);
+      }
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_anomalies_against_baseline() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::with_response_body("{\"id\": 1}").with_override("value1", 500, "Internal Server Error");
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_baseline(3, 1.0);
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: "1"
+          description: "test description"
+          values: ["value1", "2"]
+      "#).unwrap();
+    // expected_status is not checked once there is a baseline
+    let path = Path::new("/users".into(), HttpMethod::GET, 404, vec![], "".into(), vec![QueryParameter::new("id".into(), true, Some("{fuzz}".into()))], vec![], "".into())
+      .with_attack(AttackStrategy::Sniper, vec![FuzzPoint::new("fuzz".into(), vec![], "1".into())]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![path]);
+
+    fuzzer.fuzz(&input_data, Arc::new(dict)).await.unwrap();
+
+    let urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    assert_eq!(urls.len(), 5);
+    assert_eq!(urls[0], "https://example.com/api/users?id=1");
+    assert_ne!(urls[1], urls[2]);
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(fuzzed_url, failure) => {
+        assert_eq!(fuzzed_url.payloads()[0].value(), "value1");
+        assert!(matches!(&failure.reasons()[0], FailureReason::Anomaly { deviations, .. } if deviations[0] == "status 500 not in baseline {200}"));
+      }
+      result => panic!("expected a failure, got {:?}", result),
     }
+    assert!(matches!(rx.recv().await.unwrap(), FuzzingResult::Success(_, _)));
+  }
+
+  fn signature_dictionary() -> FuzzyDictionary {
+    serde_yaml::from_str(r#"
+      data:
+        - id: sqlInjectionTest
+          description: sql
+          values: ["'", "2"]
+      signatures:
+        - id: mysqlError
+          description: mysql
+          patterns: ['You have an error in your SQL syntax']
+        - id: phpError
+          description: php
+          patterns: ['<b>Warning</b>:']
+      "#).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_reports_signatures_whatever_the_status() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::with_response_body("{\"id\": 1}")
+      .with_override("id='", 200, "You have an error in your SQL syntax near 'x' at line 1");
+    let fuzzer = Fuzzer::with_client(client, 1, tx);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(signature_dictionary())).await.unwrap();
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(fuzzed_url, failure) => {
+        assert_eq!(fuzzed_url.payloads()[0].value(), "'");
+        assert_eq!(failure.status_code(), &Some(200));
+        assert_eq!(failure.reasons(), &vec![FailureReason::Signature {
+          id: "mysqlError".into(),
+          matched: "You have an error in your SQL syntax".into(),
+        }]);
+      }
+      result => panic!("expected a failure, got {:?}", result),
+    }
+    assert!(matches!(rx.recv().await.unwrap(), FuzzingResult::Success(_, _)));
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_ignores_signatures_found_in_the_baseline() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::with_response_body("<b>Warning</b>: deprecated")
+      .with_override("id='", 200, "<b>Warning</b>: deprecated You have an error in your SQL syntax");
+    let fuzzer = Fuzzer::with_client(client, 1, tx).with_baseline(2, 10.0).with_delay_factor(0.0);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(signature_dictionary())).await.unwrap();
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => {
+        assert!(matches!(&failure.reasons()[..], [FailureReason::Signature { id, .. }] if id == "mysqlError"));
+      }
+      result => panic!("expected a failure, got {:?}", result),
+    }
+    assert!(matches!(rx.recv().await.unwrap(), FuzzingResult::Success(_, _)));
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_is_rate_limited() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::with_response_body("{\"id\": 1}");
+    let fuzzer = Fuzzer::with_client(client.clone(), 10, tx).with_rate_limiter(RateLimiter::new(10.0, 0.0, 1));
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    let started = Instant::now();
+    fuzzer.fuzz(&input_data, Arc::new(signature_dictionary())).await.unwrap();
+
+    assert_eq!(client.requests().len(), 2);
+    assert!(started.elapsed() >= Duration::from_millis(90), "took {:?}", started.elapsed());
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_with_selected_dictionaries() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::with_response_body("{\"id\": 1}");
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_selection(Selection::new(&["tag:sqli".into()], &[]));
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: numericStrings
+          description: numbers
+          values: ["-1"]
+        - id: sqlInjectionTest
+          description: sql
+          tags: [sqli]
+          values: ["'"]
+      "#).unwrap();
+    let numeric = sleep_path().with_selection(vec!["numericStrings".into()], vec![]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path(), numeric]);
+
+    fuzzer.fuzz(&input_data, Arc::new(dict)).await.unwrap();
+
+    let mut urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    urls.sort();
+    assert_eq!(urls, vec!["https://example.com/api/users?id='", "https://example.com/api/users?id=-1"]);
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_generates_requests_as_results_are_consumed() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(1);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 2, tx);
+    let values = (0..100_000).map(|value| value.to_string()).collect();
+    let dict = FuzzyDictionary::new(vec![FuzzyData::new("numbers".into(), "numbers".into(), values)]);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sample_path(HttpMethod::GET)]);
+
+    let consumer = async move {
+      for _ in 0..3 {
+        rx.recv().await.unwrap();
+      }
+    };
+    let (result, _) = tokio::join!(fuzzer.fuzz(&input_data, Arc::new(dict)), consumer);
+
+    assert!(result.is_err());
+    assert!(client.requests().len() < 10);
+  }
+
+  #[tokio::test]
+  async fn test_capture_cuts_long_bodies() {
+    let response = Response::from(http::Response::new("a".repeat(CAPTURED_BODY_LIMIT + 10)));
+
+    let captured = CapturedResponse::capture(response, Instant::now()).await.unwrap();
+    assert_eq!(captured.body().len(), CAPTURED_BODY_LIMIT);
+  }
+
+  #[test]
+  fn test_failure_to_string_truncates_the_body() {
+    let response = CapturedResponse::new(500, HeaderMap::new(), "a".repeat(RESPONSE_BODY_LIMIT + 10), Duration::from_millis(5));
+    let failure = FuzzingFailure::new(None, Some(500), Some(response));
+
+    let text = failure.failure_to_string(FuzzedUrl::new("https://example.com".into(), String::new(), String::new()));
+    assert!(text.contains(&format!("response_body: Some(\"{}\"), response_body_truncated: true", "a".repeat(RESPONSE_BODY_LIMIT))));
+  }
+
+  fn sleep_path() -> Path {
+    Path::new("/users".into(), HttpMethod::GET, 200, vec![], "".into(), vec![QueryParameter::new("id".into(), true, Some("{fuzz}".into()))], vec![], "".into())
+      .with_attack(AttackStrategy::Sniper, vec![FuzzPoint::new("fuzz".into(), vec![], "1".into())])
+  }
+
+  fn sleep_dictionary() -> FuzzyDictionary {
+    serde_yaml::from_str(r#"
+      data:
+        - id: timeBasedSqlInjection
+          description: sleep
+          values: ["1 AND SLEEP(1)"]
+      "#).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_confirms_delays() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default()
+      .with_delay("SLEEP(1)", Duration::from_millis(1100))
+      .with_delay("SLEEP(2)", Duration::from_millis(2200));
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_baseline(2, 1.0).with_delay_factor(5.0);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sleep_dictionary())).await.unwrap();
+
+    let urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    assert_eq!(urls.len(), 4);
+    assert_eq!(urls[3], "https://example.com/api/users?id=1 AND SLEEP(2)");
+
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => match failure.reasons().last().unwrap() {
+        FailureReason::Delayed { latency_ms, confirmation_latency_ms, .. } => {
+          assert!(*latency_ms >= 1100.0);
+          assert!(confirmation_latency_ms.unwrap() >= 2200.0);
+        }
+        reason => panic!("expected a delay, got {:?}", reason),
+      },
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_confirms_delays_with_default_settings() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default()
+      .with_delay("SLEEP(1)", Duration::from_millis(1100))
+      .with_delay("SLEEP(2)", Duration::from_millis(2200));
+    let settings = Settings::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), settings.concurrency(), tx)
+      .with_baseline(settings.baseline_requests(), settings.anomaly_threshold())
+      .with_delay_factor(settings.delay_factor());
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sleep_dictionary())).await.unwrap();
+
+    let urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    assert_eq!(urls.len(), 5);
+    assert_eq!(urls[0], "https://example.com/api/users?id=1");
+    assert_eq!(urls[3..], ["https://example.com/api/users?id=1 AND SLEEP(1)", "https://example.com/api/users?id=1 AND SLEEP(2)"]);
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => assert!(matches!(failure.reasons().last().unwrap(), FailureReason::Delayed { .. })),
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_confirms_delays_without_a_baseline() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default()
+      .with_delay("SLEEP(1)", Duration::from_millis(1100))
+      .with_delay("SLEEP(2)", Duration::from_millis(2200));
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_baseline(0, 1.0).with_delay_factor(5.0);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sleep_dictionary())).await.unwrap();
+
+    let urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    assert_eq!(urls, vec![
+      "https://example.com/api/users?id=1 AND SLEEP(1)",
+      "https://example.com/api/users?id=1",
+      "https://example.com/api/users?id=1 AND SLEEP(2)",
+    ]);
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => assert!(matches!(failure.reasons().last().unwrap(), FailureReason::Delayed { .. })),
+      result => panic!("expected a failure, got {:?}", result),
+    }
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_ignores_unconfirmed_delays() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default().with_delay("SLEEP", Duration::from_millis(1100));
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_baseline(2, 10.0);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sleep_dictionary())).await.unwrap();
+
+    assert_eq!(client.requests().len(), 4);
+    assert!(matches!(rx.recv().await.unwrap(), FuzzingResult::Success(_, _)));
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_ignores_a_single_slow_response() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default().with_first_delay("value1", Duration::from_millis(1100));
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx).with_baseline(2, 1.0).with_delay_factor(5.0);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sleep_path()]);
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    let urls: Vec<String> = client.requests().iter().map(|request| request.url.clone()).collect();
+    assert_eq!(urls.len(), 4);
+    assert_eq!(urls[2], urls[3]);
+    match rx.recv().await.unwrap() {
+      FuzzingResult::Failure(_, failure) => assert!(!failure.reasons().iter().any(|reason| matches!(reason, FailureReason::Delayed { .. }))),
+      FuzzingResult::Success(_, _) => {}
+      result => panic!("expected a response, got {:?}", result),
+    }
+  }
+
+  #[test]
+  fn test_generate_fuzzed_urls_with_cluster_bomb() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(1);
+    let fuzzer = Fuzzer::new(1, tx);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![]);
+    let dict: FuzzyDictionary = serde_yaml::from_str(r#"
+      data:
+        - id: numbers
+          description: numbers
+          values: ["1", "2"]
+        - id: letters
+          description: letters
+          values: ["a"]
+      "#).unwrap();
+    let path = Path::new(
+      "/users/{fuzz_id}".into(),
+      HttpMethod::GET,
+      200,
+      vec![],
+      "".into(),
+      vec![QueryParameter::new("type".into(), true, Some("{fuzz_type}".into()))],
+      vec![],
+      "".into()
+    ).with_attack(AttackStrategy::ClusterBomb, vec![
+      FuzzPoint::new("fuzz_id".into(), vec!["numbers".into()], "0".into()),
+      FuzzPoint::new("fuzz_type".into(), vec!["letters".into()], "".into()),
+    ]);
+    let url = fuzzer.generate_url(&input_data, &path).unwrap();
+
+    let template = RequestTemplate::new(&url, &path);
+    let points = fuzzer.attack_points(&template, &path, &dict);
+    let fuzzed_urls: Vec<FuzzedUrl> = fuzzer.generate_fuzzed_urls(&template, &points, path.attack(), &None).collect();
+    let urls: Vec<&String> = fuzzed_urls.iter().map(|fuzzed_url| fuzzed_url.url()).collect();
+    assert_eq!(urls, vec!["https://example.com/api/users/1?type=a", "https://example.com/api/users/2?type=a"]);
+    assert_eq!(fuzzed_urls[0].id(), "numbers+letters");
+    assert_eq!(fuzzed_urls[0].payloads().len(), 2);
+  }
+
+  #[test]
+  fn test_generate_fuzzed_urls_with_sniper_uses_defaults() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(1);
+    let fuzzer = Fuzzer::new(1, tx);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![]);
+    let path = Path::new(
+      "/users/{fuzz_id}".into(),
+      HttpMethod::GET,
+      200,
+      vec![],
+      "".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![],
+      "".into()
+    ).with_attack(AttackStrategy::Sniper, vec![FuzzPoint::new("fuzz_id".into(), vec![], "42".into())]);
+    let url = fuzzer.generate_url(&input_data, &path).unwrap();
+
+    let template = RequestTemplate::new(&url, &path);
+    let dict = sample_dictionary();
+    let points = fuzzer.attack_points(&template, &path, &dict);
+    let fuzzed_urls: Vec<FuzzedUrl> = fuzzer.generate_fuzzed_urls(&template, &points, path.attack(), &None).collect();
+    let urls: Vec<&String> = fuzzed_urls.iter().map(|fuzzed_url| fuzzed_url.url()).collect();
+    assert_eq!(urls, vec!["https://example.com/api/users/value1?q=", "https://example.com/api/users/42?q=value1"]);
+  }
+
+  #[test]
+  fn test_generate_fuzzed_urls_with_transforms() {
+    let (tx, _rx) = tokio::sync::mpsc::channel::<FuzzingResult>(1);
+    let fuzzer = Fuzzer::new(1, tx);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![]);
+    let path = Path::new("/users/{fuzz_id}".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], vec![], "".into())
+      .with_attack(AttackStrategy::Sniper, vec![FuzzPoint::new("fuzz_id".into(), vec![], "".into()).with_transforms(vec![
+        vec![],
+        vec![Transform::Prefix("'".into()), Transform::Base64],
+      ])]);
+    let url = fuzzer.generate_url(&input_data, &path).unwrap();
+
+    let template = RequestTemplate::new(&url, &path);
+    let dict = sample_dictionary();
+    let points = fuzzer.attack_points(&template, &path, &dict);
+    let fuzzed_urls: Vec<FuzzedUrl> = fuzzer.generate_fuzzed_urls(&template, &points, path.attack(), &None).collect();
+    let urls: Vec<&String> = fuzzed_urls.iter().map(|fuzzed_url| fuzzed_url.url()).collect();
+    // the transformed value is still percent encoded for the path
+    assert_eq!(urls, vec!["https://example.com/api/users/value1?", "https://example.com/api/users/J3ZhbHVlMQ%3D%3D?"]);
+    assert_eq!(fuzzed_urls[1].payloads()[0].value(), "J3ZhbHVlMQ==");
+  }
+
+  #[tokio::test]
+  async fn test_fuzz_injects_request_id_header() {
+    let (tx, mut rx) = tokio::sync::mpsc::channel::<FuzzingResult>(8);
+    let client = MockClient::default();
+    let fuzzer = Fuzzer::with_client(client.clone(), 1, tx);
+    let input_data = FuzzyInput::new("https://example.com".into(), "/api".into(), vec![sample_path(HttpMethod::GET)])
+      .with_request_id_header(Some("X-Fuzzy-Request-Id".into()));
+
+    fuzzer.fuzz(&input_data, Arc::new(sample_dictionary())).await.unwrap();
+
+    let result = rx.recv().await.unwrap();
+    let requests = client.requests();
+    assert_eq!(requests[0].headers.get("X-Fuzzy-Request-Id").unwrap(), result.fuzzed_url().request_id().to_string().as_str());
+  }
+
+  #[test]
+  fn test_fuzzed_urls_have_unique_request_ids() {
+    let first = FuzzedUrl::new("https://example.com".into(), "desc".into(), "id".into());
+    let second = FuzzedUrl::new("https://example.com".into(), "desc".into(), "id".into());
+
+    assert_ne!(first.request_id(), second.request_id());
+    assert_eq!(first.clone().request_id(), first.request_id());
+  }
+
+  #[test]
+  fn test_fuzzed_url_merges_dictionary_metadata() {
+    let sql = Arc::new(Metadata::new(vec![], vec!["sqli".into()], Some(Severity::High), vec![]));
+    let shellshock = Arc::new(Metadata::new(vec!["CVE-2014-6271".into()], vec!["rce".into()], Some(Severity::Critical), vec![]));
+    let url = FuzzedUrl::new("https://example.com".into(), String::new(), String::new()).with_payloads(vec![
+      InjectedPayload::new("fuzz1".into(), "'".into(), "sqlInjectionTest".into(), "sql".into()).with_metadata(sql),
+      InjectedPayload::new("fuzz2".into(), "() { 0; };".into(), "shellShockCheck".into(), "shellshock".into()).with_metadata(shellshock),
+    ]);
+
+    assert_eq!(url.id(), "sqlInjectionTest+shellShockCheck");
+    assert_eq!(url.metadata().severity(), Some(Severity::Critical));
+    assert_eq!(url.metadata().cves(), &vec!["CVE-2014-6271".to_string()]);
+    assert_eq!(url.metadata().tags(), &vec!["sqli".to_string(), "rce".to_string()]);
   }
 
   #[tokio::test]
@@ -0,0 +1,395 @@
+use std::fmt;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use serde_json::Value;
+use crate::domain::input::{BodyMatch, ExpectedHeader, HeaderMatch, Path, ReflectionMode};
+use crate::fuzzer::attack::InjectedPayload;
+use crate::fuzzer::fuzzy::FailureReason;
+use crate::fuzzer::reflection::find_reflections;
+
+const JSON_WILDCARD: &str = "*";
+
+#[derive(Debug)]
+pub enum MatcherError {
+  InvalidRegex(regex::Error),
+  InvalidJson(serde_json::Error),
+}
+
+impl fmt::Display for MatcherError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      MatcherError::InvalidRegex(err) => write!(f, "invalid regex: {}", err),
+      MatcherError::InvalidJson(err) => write!(f, "invalid expected_body json: {}", err),
+    }
+  }
+}
+
+impl From<regex::Error> for MatcherError {
+  fn from(err: regex::Error) -> Self {
+    MatcherError::InvalidRegex(err)
+  }
+}
+
+/// Compiled expectations of a path, built once per path and shared by all of its requests.
+#[derive(Debug)]
+pub struct ResponseMatcher {
+  expected_status: u16,
+  expected_headers: Vec<HeaderMatcher>,
+  expected_body: Option<BodyMatcher>,
+  reflection: ReflectionMode,
+}
+
+#[derive(Debug)]
+enum BodyMatcher {
+  Pattern(BodyMatch, Regex),
+  Json(Value),
+}
+
+impl BodyMatcher {
+  fn new(expected_body: &str, body_match: &BodyMatch) -> Result<Option<Self>, MatcherError> {
+    if expected_body.trim().is_empty() {
+      return Ok(None);
+    }
+
+    let matcher = match body_match {
+      BodyMatch::Wildcard => {
+        let pattern = expected_body.trim()
+          .split('*')
+          .map(regex::escape)
+          .collect::<Vec<_>>()
+          .join(".*");
+        BodyMatcher::Pattern(BodyMatch::Wildcard, Regex::new(&format!("(?s)^{}$", pattern))?)
+      }
+      BodyMatch::Regex => BodyMatcher::Pattern(BodyMatch::Regex, Regex::new(expected_body)?),
+      BodyMatch::Json => BodyMatcher::Json(serde_json::from_str(expected_body).map_err(MatcherError::InvalidJson)?),
+    };
+
+    Ok(Some(matcher))
+  }
+
+  fn check(&self, body: &str) -> Option<FailureReason> {
+    match self {
+      BodyMatcher::Pattern(BodyMatch::Wildcard, regex) if !regex.is_match(body.trim()) => Some(FailureReason::BodyMismatch {
+        match_mode: BodyMatch::Wildcard,
+        detail: "body does not match the expected body".into(),
+      }),
+      BodyMatcher::Pattern(match_mode, regex) if !regex.is_match(body) => Some(FailureReason::BodyMismatch {
+        match_mode: match_mode.clone(),
+        detail: format!("body does not match {}", regex),
+      }),
+      BodyMatcher::Pattern(_, _) => None,
+      BodyMatcher::Json(expected) => {
+        let differences = match serde_json::from_str::<Value>(body) {
+          Ok(actual) => {
+            let mut differences = Vec::new();
+            json_differences(expected, &actual, "$", &mut differences);
+            differences
+          }
+          Err(err) => vec![format!("body is not valid json: {}", err)],
+        };
+
+        if differences.is_empty() {
+          None
+        } else {
+          Some(FailureReason::BodyMismatch { match_mode: BodyMatch::Json, detail: differences.join("; ") })
+        }
+      }
+    }
+  }
+}
+
+/// Compares two json documents structurally, objects are compared by key so their order does not matter.
+fn json_differences(expected: &Value, actual: &Value, path: &str, differences: &mut Vec<String>) {
+  match (expected, actual) {
+    (Value::String(wildcard), _) if wildcard == JSON_WILDCARD => {}
+    (Value::Object(expected), Value::Object(actual)) => {
+      for (key, expected_value) in expected {
+        match actual.get(key) {
+          Some(actual_value) => json_differences(expected_value, actual_value, &format!("{}.{}", path, key), differences),
+          None => differences.push(format!("{}.{}: missing", path, key)),
+        }
+      }
+
+      for key in actual.keys().filter(|key| !expected.contains_key(*key)) {
+        differences.push(format!("{}.{}: unexpected field", path, key));
+      }
+    }
+    (Value::Array(expected), Value::Array(actual)) => {
+      if expected.len() != actual.len() {
+        differences.push(format!("{}: expected {} elements, got {}", path, expected.len(), actual.len()));
+      }
+
+      for (index, (expected_value, actual_value)) in expected.iter().zip(actual).enumerate() {
+        json_differences(expected_value, actual_value, &format!("{}[{}]", path, index), differences);
+      }
+    }
+    _ if expected == actual => {}
+    _ => differences.push(format!("{}: expected {}, got {}", path, expected, actual)),
+  }
+}
+
+#[derive(Debug)]
+struct HeaderMatcher {
+  name: String,
+  value: String,
+  regex: Option<Regex>,
+  match_mode: HeaderMatch,
+}
+
+impl HeaderMatcher {
+  fn new(header: &ExpectedHeader) -> Result<Self, regex::Error> {
+    let regex = match header.match_mode() {
+      HeaderMatch::Regex => Some(Regex::new(header.value())?),
+      _ => None,
+    };
+
+    Ok(Self {
+      name: header.name().clone(),
+      value: header.value().clone(),
+      regex,
+      match_mode: header.match_mode().clone(),
+    })
+  }
+
+  fn matches(&self, actual: &str) -> bool {
+    match self.match_mode {
+      HeaderMatch::Exact => actual == self.value,
+      HeaderMatch::CaseInsensitive => actual.eq_ignore_ascii_case(&self.value),
+      HeaderMatch::Prefix => actual.starts_with(&self.value),
+      HeaderMatch::Regex => self.regex.as_ref().is_some_and(|regex| regex.is_match(actual)),
+    }
+  }
+}
+
+impl ResponseMatcher {
+  pub fn new(path: &Path) -> Result<Self, MatcherError> {
+    let expected_headers = path.expected_headers()
+      .iter()
+      .map(HeaderMatcher::new)
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Self {
+      expected_status: *path.expected_status(),
+      expected_headers,
+      expected_body: BodyMatcher::new(path.expected_body(), path.body_match())?,
+      reflection: path.reflection().clone(),
+    })
+  }
+
+  /// Checks each injected payload on its own, a request fuzzing several placeholders can report each of them.
+  pub fn check_reflection(&self, payloads: &[InjectedPayload], headers: &HeaderMap, body: &str) -> Vec<FailureReason> {
+    payloads
+      .iter()
+      .filter_map(|payload| {
+        let point = payload.point().clone();
+        match self.reflection {
+          ReflectionMode::Off => None,
+          ReflectionMode::Reflected => {
+            let locations = find_reflections(payload.value(), headers, body);
+            if locations.is_empty() { None } else { Some(FailureReason::Reflected { point, locations }) }
+          }
+          ReflectionMode::NotReflected => {
+            if find_reflections(payload.value(), headers, body).is_empty() { Some(FailureReason::NotReflected { point }) } else { None }
+          }
+        }
+      })
+      .collect()
+  }
+
+  pub fn check_body(&self, body: &str) -> Option<FailureReason> {
+    self.expected_body.as_ref().and_then(|matcher| matcher.check(body))
+  }
+
+  pub fn check_status(&self, status: u16) -> Option<FailureReason> {
+    if status != self.expected_status {
+      Some(FailureReason::UnexpectedStatus { expected: self.expected_status, actual: status })
+    } else {
+      None
+    }
+  }
+
+  /// Returns a reason for every expected header that is missing or where none of the received values match.
+  pub fn check_headers(&self, headers: &HeaderMap) -> Vec<FailureReason> {
+    self.expected_headers
+      .iter()
+      .filter_map(|expected| {
+        let values: Vec<String> = headers.get_all(expected.name.as_str())
+          .iter()
+          .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+          .collect();
+
+        if values.iter().any(|value| expected.matches(value)) {
+          None
+        } else {
+          Some(FailureReason::HeaderMismatch {
+            name: expected.name.clone(),
+            expected: expected.value.clone(),
+            match_mode: expected.match_mode.clone(),
+            actual: if values.is_empty() { None } else { Some(values.join(", ")) },
+          })
+        }
+      })
+      .collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use reqwest::header::HeaderValue;
+  use crate::domain::input::HttpMethod;
+  use super::*;
+
+  fn sample_matcher(expected_headers: Vec<ExpectedHeader>) -> ResponseMatcher {
+    let path = Path::new("/test".into(), HttpMethod::GET, 200, expected_headers, "".into(), vec![], vec![], "".into());
+    ResponseMatcher::new(&path).unwrap()
+  }
+
+  fn sample_body_matcher(expected_body: &str, body_match: BodyMatch) -> ResponseMatcher {
+    let path = Path::new("/test".into(), HttpMethod::GET, 200, vec![], expected_body.into(), vec![], vec![], "".into())
+      .with_body_match(body_match);
+    ResponseMatcher::new(&path).unwrap()
+  }
+
+  fn sample_headers() -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static("application/json; charset=utf-8"));
+    headers.insert("x-powered-by", HeaderValue::from_static("Express"));
+    headers
+  }
+
+  #[test]
+  fn test_check_status() {
+    let matcher = sample_matcher(vec![]);
+
+    assert_eq!(matcher.check_status(200), None);
+    assert_eq!(matcher.check_status(500), Some(FailureReason::UnexpectedStatus { expected: 200, actual: 500 }));
+  }
+
+  #[test]
+  fn test_check_headers_match_modes() {
+    let matcher = sample_matcher(vec![
+      ExpectedHeader::new("Content-Type".into(), "application/json; charset=utf-8".into(), HeaderMatch::Exact),
+      ExpectedHeader::new("X-Powered-By".into(), "EXPRESS".into(), HeaderMatch::CaseInsensitive),
+      ExpectedHeader::new("Content-Type".into(), "application/json".into(), HeaderMatch::Prefix),
+      ExpectedHeader::new("Content-Type".into(), "^application/(.+\\+)?json".into(), HeaderMatch::Regex),
+    ]);
+
+    assert!(matcher.check_headers(&sample_headers()).is_empty());
+  }
+
+  #[test]
+  fn test_check_headers_lists_every_mismatch() {
+    let matcher = sample_matcher(vec![
+      ExpectedHeader::new("Content-Type".into(), "text/html".into(), HeaderMatch::Prefix),
+      ExpectedHeader::new("X-Frame-Options".into(), "DENY".into(), HeaderMatch::Exact),
+      ExpectedHeader::new("X-Powered-By".into(), "Express".into(), HeaderMatch::Exact),
+    ]);
+
+    let reasons = matcher.check_headers(&sample_headers());
+    assert_eq!(reasons, vec![
+      FailureReason::HeaderMismatch {
+        name: "Content-Type".into(),
+        expected: "text/html".into(),
+        match_mode: HeaderMatch::Prefix,
+        actual: Some("application/json; charset=utf-8".into()),
+      },
+      FailureReason::HeaderMismatch {
+        name: "X-Frame-Options".into(),
+        expected: "DENY".into(),
+        match_mode: HeaderMatch::Exact,
+        actual: None,
+      },
+    ]);
+  }
+
+  #[test]
+  fn test_invalid_regex_is_rejected() {
+    let path = Path::new(
+      "/test".into(),
+      HttpMethod::GET,
+      200,
+      vec![ExpectedHeader::new("Content-Type".into(), "(".into(), HeaderMatch::Regex)],
+      "".into(),
+      vec![],
+      vec![],
+      "".into()
+    );
+
+    assert!(ResponseMatcher::new(&path).is_err());
+  }
+
+  #[test]
+  fn test_empty_expected_body_is_not_checked() {
+    let matcher = sample_body_matcher("  \n", BodyMatch::Json);
+    assert_eq!(matcher.check_body("anything"), None);
+  }
+
+  #[test]
+  fn test_check_body_wildcard() {
+    let matcher = sample_body_matcher("{\"user\": \"*\", \"id\": *}\n", BodyMatch::Wildcard);
+
+    assert_eq!(matcher.check_body("{\"user\": \"a.b\", \"id\": 12}"), None);
+    assert!(matcher.check_body("{\"user\": \"a\"}").is_some());
+    assert!(matcher.check_body("prefix {\"user\": \"a\", \"id\": 1}").is_some());
+  }
+
+  #[test]
+  fn test_check_body_regex() {
+    let matcher = sample_body_matcher("^\\{.*\"ok\":\\s*true", BodyMatch::Regex);
+
+    assert_eq!(matcher.check_body("{\"ok\": true}"), None);
+    assert!(matcher.check_body("at com.example.Controller.handle(Controller.java:42)").is_some());
+  }
+
+  #[test]
+  fn test_check_body_json_ignores_key_order_and_wildcards() {
+    let matcher = sample_body_matcher(r#"{ "hello": "world", "id": "*", "items": [1, { "a": true }] }"#, BodyMatch::Json);
+
+    assert_eq!(matcher.check_body(r#"{"items": [1, {"a": true}], "id": 7, "hello": "world"}"#), None);
+  }
+
+  #[test]
+  fn test_check_body_json_lists_differences() {
+    let matcher = sample_body_matcher(r#"{ "hello": "world", "items": [1, 2] }"#, BodyMatch::Json);
+
+    assert_eq!(
+      matcher.check_body(r#"{"hello": "there", "items": [1], "trace": "..."}"#),
+      Some(FailureReason::BodyMismatch {
+        match_mode: BodyMatch::Json,
+        detail: "$.hello: expected \"world\", got \"there\"; $.items: expected 2 elements, got 1; $.trace: unexpected field".into(),
+      })
+    );
+    assert!(matcher.check_body("Internal Server Error").is_some());
+  }
+
+  #[test]
+  fn test_invalid_expected_json_is_rejected() {
+    let path = Path::new("/test".into(), HttpMethod::GET, 200, vec![], "{ nope".into(), vec![], vec![], "".into())
+      .with_body_match(BodyMatch::Json);
+
+    assert!(matches!(ResponseMatcher::new(&path), Err(MatcherError::InvalidJson(_))));
+  }
+
+  #[test]
+  fn test_check_reflection() {
+    let path = Path::new("/test".into(), HttpMethod::GET, 200, vec![], "".into(), vec![], vec![], "".into());
+    let headers = HeaderMap::new();
+    let payloads = vec![
+      InjectedPayload::new("fuzz1".into(), "<b>".into(), "id".into(), "description".into()),
+      InjectedPayload::new("fuzz2".into(), "<i>".into(), "id".into(), "description".into()),
+    ];
+
+    let off = ResponseMatcher::new(&path).unwrap();
+    assert!(off.check_reflection(&payloads, &headers, "<b>").is_empty());
+
+    let reflected = ResponseMatcher::new(&path.clone().with_reflection(ReflectionMode::Reflected)).unwrap();
+    assert_eq!(reflected.check_reflection(&payloads, &headers, "&lt;b&gt;"), vec![
+      FailureReason::Reflected { point: "fuzz1".into(), locations: vec!["body (html decoded)".into()] },
+    ]);
+    assert!(reflected.check_reflection(&payloads, &headers, "nothing").is_empty());
+
+    let not_reflected = ResponseMatcher::new(&path.with_reflection(ReflectionMode::NotReflected)).unwrap();
+    assert_eq!(not_reflected.check_reflection(&payloads, &headers, "<i>"), vec![FailureReason::NotReflected { point: "fuzz1".into() }]);
+    assert!(not_reflected.check_reflection(&payloads, &headers, "<b><i>").is_empty());
+  }
+}
@@ -8,6 +8,7 @@ pub struct Metrics {
   pub start_time: Instant,
   pub successful_requests: u64,
   pub failed_requests: u64,
+  pub build_errors: u64,
   pub total_requests: u64,
 }
 
@@ -17,23 +18,25 @@ impl Metrics {
       start_time: Instant::now(),
       successful_requests: 0,
       failed_requests: 0,
+      build_errors: 0,
       total_requests: 0,
     }))
   }
 
   pub fn display(&self) {
-    let elapsed_seconds = self.start_time.clone().elapsed().as_secs_f64();
+    let elapsed_seconds = self.start_time.elapsed().as_secs_f64();
 
     let throughput = if elapsed_seconds == 0.0 {
       0.0
     } else {
-      self.total_requests.clone() as f64 / elapsed_seconds.clone()
+      self.total_requests as f64 / elapsed_seconds
     };
 
     log::info!("============================================");
     log::info!("ℹ️ total requests: {}", self.total_requests);
     log::info!("ℹ️ successful requests: {}", self.successful_requests);
     log::info!("ℹ️ failed requests: {}", self.failed_requests);
+    log::info!("ℹ️ requests that could not be built: {}", self.build_errors);
     log::info!("ℹ️ throughput: {:.2} req/s", throughput);
     log::info!("ℹ️ time: {:.2} s", elapsed_seconds);
   }
@@ -50,6 +53,7 @@ mod tests {
 
     assert_eq!(metrics.successful_requests, 0);
     assert_eq!(metrics.failed_requests, 0);
+    assert_eq!(metrics.build_errors, 0);
     assert_eq!(metrics.total_requests, 0);
   }
 
@@ -1,4 +1,14 @@
+pub mod attack;
+pub mod baseline;
+pub mod body;
 pub mod fuzzy;
+pub mod matcher;
 pub mod metrics;
+pub mod output;
+pub mod rate_limit;
+pub mod reflection;
+pub mod signature;
+pub mod template;
+pub mod timing;
 pub mod data_channels;
-pub mod result_aggregator;
\ No newline at end of file
+pub mod result_aggregator;
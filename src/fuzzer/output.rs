@@ -0,0 +1,235 @@
+use std::collections::BTreeMap;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use crate::domain::dictionary::Severity;
+use crate::domain::input::HttpMethod;
+use crate::fuzzer::attack::InjectedPayload;
+use crate::fuzzer::fuzzy::{CapturedResponse, FailureReason, FuzzingResult};
+
+/// Response bodies longer than this are cut in output records.
+pub const RESPONSE_BODY_LIMIT: usize = 4096;
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+  /// One line per failed request or request that could not be built.
+  #[default]
+  Text,
+  /// One json object per result.
+  Jsonl,
+}
+
+impl OutputFormat {
+  /// Picks json lines for `.jsonl` and `.ndjson` files and text for anything else.
+  pub fn from_path(path: &str) -> Self {
+    if path.ends_with(".jsonl") || path.ends_with(".ndjson") {
+      OutputFormat::Jsonl
+    } else {
+      OutputFormat::Text
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+  Success,
+  Failure,
+  /// The request could not be built, so it was never sent.
+  BuildError,
+}
+
+/// A result as written to the json lines output.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ResultRecord {
+  request_id: String,
+  outcome: Outcome,
+  method: HttpMethod,
+  url: String,
+  request_headers: BTreeMap<String, String>,
+  request_body: String,
+  payloads: Vec<InjectedPayload>,
+  dictionary_id: String,
+  dictionary_description: String,
+  severity: Option<Severity>,
+  cves: Vec<String>,
+  tags: Vec<String>,
+  references: Vec<String>,
+  status: Option<u16>,
+  latency_ms: Option<f64>,
+  response_headers: Option<BTreeMap<String, String>>,
+  response_body: Option<String>,
+  response_body_truncated: bool,
+  reasons: Vec<FailureReason>,
+  error: Option<String>,
+}
+
+impl ResultRecord {
+  pub fn new(result: &FuzzingResult) -> Self {
+    let fuzzed_url = result.fuzzed_url();
+    let (outcome, response, latency, reasons, error) = match result {
+      FuzzingResult::Success(_, response) => (Outcome::Success, Some(response), Some(response.latency()), Vec::new(), None),
+      FuzzingResult::Failure(_, failure) => (
+        Outcome::Failure,
+        failure.response().as_ref(),
+        failure.latency(),
+        failure.reasons().clone(),
+        failure.network_error().as_ref().map(|err| err.to_string()),
+      ),
+      FuzzingResult::BuildError(_, err) => (Outcome::BuildError, None, None, Vec::new(), Some(err.to_string())),
+    };
+
+    let mut request_headers = BTreeMap::new();
+    for (name, value) in fuzzed_url.headers() {
+      request_headers.entry(name.clone())
+        .and_modify(|existing: &mut String| *existing = format!("{}, {}", existing, value))
+        .or_insert_with(|| value.clone());
+    }
+
+    let (response_body, response_body_truncated) = match response {
+      Some(response) => {
+        let (body, truncated) = truncate(response.body(), RESPONSE_BODY_LIMIT);
+        (Some(body), truncated)
+      }
+      None => (None, false),
+    };
+
+    Self {
+      request_id: fuzzed_url.request_id().to_string(),
+      outcome,
+      method: fuzzed_url.method().clone(),
+      url: fuzzed_url.url().clone(),
+      request_headers,
+      request_body: fuzzed_url.body().clone(),
+      payloads: fuzzed_url.payloads().clone(),
+      dictionary_id: fuzzed_url.id().clone(),
+      dictionary_description: fuzzed_url.description().clone(),
+      severity: fuzzed_url.metadata().severity(),
+      cves: fuzzed_url.metadata().cves().clone(),
+      tags: fuzzed_url.metadata().tags().clone(),
+      references: fuzzed_url.metadata().references().clone(),
+      status: response.map(CapturedResponse::status),
+      latency_ms: latency.map(|latency| latency.as_secs_f64() * 1000.0),
+      response_headers: response.map(|response| header_map(response.headers())),
+      response_body,
+      response_body_truncated,
+      reasons,
+      error,
+    }
+  }
+
+  pub fn to_json(&self) -> String {
+    serde_json::to_string(self).expect("result records are always serializable")
+  }
+}
+
+fn header_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+  let mut map = BTreeMap::new();
+  for name in headers.keys() {
+    let values: Vec<String> = headers.get_all(name)
+      .iter()
+      .map(|value| String::from_utf8_lossy(value.as_bytes()).to_string())
+      .collect();
+    map.insert(name.to_string(), values.join(", "));
+  }
+
+  map
+}
+
+/// Cuts the text to at most `limit` bytes without splitting a character.
+pub fn truncate(text: &str, limit: usize) -> (String, bool) {
+  if text.len() <= limit {
+    return (text.to_string(), false);
+  }
+
+  let mut end = limit;
+  while !text.is_char_boundary(end) {
+    end -= 1;
+  }
+
+  (text[..end].to_string(), true)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::Arc;
+  use std::time::Duration;
+  use reqwest::header::HeaderValue;
+  use serde_json::{json, Value};
+  use crate::domain::dictionary::Metadata;
+  use crate::error::RequestError;
+  use crate::fuzzer::fuzzy::{FuzzedUrl, FuzzingFailure};
+  use super::*;
+
+  fn sample_fuzzed_url() -> FuzzedUrl {
+    FuzzedUrl::new("http://test.com/test?q=%27".into(), String::new(), String::new())
+      .with_method(HttpMethod::POST)
+      .with_headers(vec![("Accept".into(), "application/json".into())])
+      .with_body("{\"q\": \"'\"}".into())
+      .with_payloads(vec![
+        InjectedPayload::new("fuzz".into(), "'".into(), "sqlInjectionTest".into(), "sql".into())
+          .with_metadata(Arc::new(Metadata::new(vec![], vec!["sqli".into()], Some(Severity::High), vec!["https://owasp.org/www-community/attacks/SQL_Injection".into()]))),
+      ])
+  }
+
+  fn sample_response(body: &str) -> CapturedResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert("content-type", HeaderValue::from_static("text/plain"));
+    CapturedResponse::new(500, headers, body.into(), Duration::from_millis(12))
+  }
+
+  #[test]
+  fn test_output_format_from_path() {
+    assert_eq!(OutputFormat::from_path("results.jsonl"), OutputFormat::Jsonl);
+    assert_eq!(OutputFormat::from_path("results.ndjson"), OutputFormat::Jsonl);
+    assert_eq!(OutputFormat::from_path("output.txt"), OutputFormat::Text);
+  }
+
+  #[test]
+  fn test_failure_record() {
+    let failure = FuzzingFailure::new(None, Some(500), Some(sample_response("syntax error")))
+      .with_reasons(vec![FailureReason::UnexpectedStatus { expected: 200, actual: 500 }]);
+    let result = FuzzingResult::Failure(sample_fuzzed_url(), failure);
+
+    let record: Value = serde_json::from_str(&ResultRecord::new(&result).to_json()).unwrap();
+    assert_eq!(record["request_id"], result.fuzzed_url().request_id().to_string());
+    assert_eq!(record["outcome"], "failure");
+    assert_eq!(record["method"], "POST");
+    assert_eq!(record["url"], "http://test.com/test?q=%27");
+    assert_eq!(record["request_headers"], json!({ "Accept": "application/json" }));
+    assert_eq!(record["request_body"], "{\"q\": \"'\"}");
+    assert_eq!(record["payloads"], json!([{ "point": "fuzz", "value": "'", "id": "sqlInjectionTest", "description": "sql" }]));
+    assert_eq!(record["dictionary_id"], "sqlInjectionTest");
+    assert_eq!(record["severity"], "high");
+    assert_eq!(record["cves"], json!([]));
+    assert_eq!(record["tags"], json!(["sqli"]));
+    assert_eq!(record["references"], json!(["https://owasp.org/www-community/attacks/SQL_Injection"]));
+    assert_eq!(record["status"], 500);
+    assert_eq!(record["latency_ms"], 12.0);
+    assert_eq!(record["response_headers"], json!({ "content-type": "text/plain" }));
+    assert_eq!(record["response_body"], "syntax error");
+    assert_eq!(record["reasons"], json!([{ "kind": "unexpected_status", "expected": 200, "actual": 500 }]));
+    assert_eq!(record["error"], Value::Null);
+  }
+
+  #[test]
+  fn test_build_error_record() {
+    let err = RequestError::InvalidHeaderValue { name: "X-Test".into(), value: "a\nb".into() };
+    let result = FuzzingResult::BuildError(sample_fuzzed_url(), err);
+
+    let record: Value = serde_json::from_str(&ResultRecord::new(&result).to_json()).unwrap();
+    assert_eq!(record["outcome"], "build_error");
+    assert_eq!(record["status"], Value::Null);
+    assert_eq!(record["error"], "invalid value for header X-Test: \"a\\nb\"");
+  }
+
+  #[test]
+  fn test_response_body_is_truncated() {
+    let body = "é".repeat(RESPONSE_BODY_LIMIT);
+    let result = FuzzingResult::Success(sample_fuzzed_url(), sample_response(&body));
+
+    let record = ResultRecord::new(&result);
+    assert!(record.response_body_truncated);
+    assert_eq!(record.response_body.unwrap().len(), RESPONSE_BODY_LIMIT);
+  }
+}
@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use url::Url;
+
+/// Tokens available, and when they were last counted.
+#[derive(Debug)]
+struct BucketState {
+  tokens: f64,
+  updated: Instant,
+}
+
+/// Lets `rate` requests through per second, after a burst of up to `burst` requests when it was idle.
+#[derive(Debug)]
+struct TokenBucket {
+  rate: f64,
+  burst: f64,
+  state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+  fn new(rate: f64, burst: usize) -> Self {
+    let burst = burst.max(1) as f64;
+    Self { rate, burst, state: Mutex::new(BucketState { tokens: burst, updated: Instant::now() }) }
+  }
+
+  /// Waits for a token. The lock is held while waiting, so waiters are let through one at a time in order.
+  async fn acquire(&self) {
+    let mut state = self.state.lock().await;
+    let now = Instant::now();
+    state.tokens = (state.tokens + now.duration_since(state.updated).as_secs_f64() * self.rate).min(self.burst);
+    state.updated = now;
+
+    if state.tokens < 1.0 {
+      tokio::time::sleep(Duration::from_secs_f64((1.0 - state.tokens) / self.rate)).await;
+      state.tokens = 1.0;
+      state.updated = Instant::now();
+    }
+
+    state.tokens -= 1.0;
+  }
+}
+
+/// A random pause of up to `max`, drawn from a seedable generator so it can be reproduced.
+#[derive(Debug)]
+struct Jitter {
+  max: Duration,
+  rng: std::sync::Mutex<StdRng>,
+}
+
+impl Default for Jitter {
+  fn default() -> Self {
+    Self { max: Duration::ZERO, rng: std::sync::Mutex::new(StdRng::from_entropy()) }
+  }
+}
+
+impl Jitter {
+  fn next(&self) -> Duration {
+    if self.max.is_zero() {
+      return Duration::ZERO;
+    }
+
+    let fraction: f64 = self.rng.lock().expect("jitter generator lock is never poisoned").gen();
+    self.max.mul_f64(fraction)
+  }
+}
+
+/// Paces requests with a global rate, a rate per host and a random pause before each request, all optional.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+  global: Option<TokenBucket>,
+  host_rate: f64,
+  burst: usize,
+  hosts: Mutex<HashMap<String, Arc<TokenBucket>>>,
+  jitter: Jitter,
+}
+
+impl RateLimiter {
+  /// Rates are in requests per second, 0 is unlimited, and `burst` requests can be sent at once after a pause.
+  pub fn new(rate: f64, host_rate: f64, burst: usize) -> Self {
+    Self {
+      global: (rate > 0.0).then(|| TokenBucket::new(rate, burst)),
+      host_rate,
+      burst,
+      ..Default::default()
+    }
+  }
+
+  /// Waits a random time up to `jitter` before each request, on top of the rates.
+  pub fn with_jitter(mut self, jitter: Duration) -> Self {
+    self.jitter.max = jitter;
+    self
+  }
+
+  /// Draws the jitter from a generator seeded with `seed`, so the same pauses are taken on every run.
+  pub fn with_jitter_seed(mut self, seed: u64) -> Self {
+    self.jitter.rng = std::sync::Mutex::new(StdRng::seed_from_u64(seed));
+    self
+  }
+
+  /// Waits until a request to the url can be sent.
+  pub async fn wait(&self, url: &str) {
+    if self.host_rate > 0.0 {
+      let bucket = {
+        let mut hosts = self.hosts.lock().await;
+        hosts.entry(host(url))
+          .or_insert_with(|| Arc::new(TokenBucket::new(self.host_rate, self.burst)))
+          .clone()
+      };
+      bucket.acquire().await;
+    }
+
+    if let Some(global) = &self.global {
+      global.acquire().await;
+    }
+
+    let pause = self.jitter.next();
+    if !pause.is_zero() {
+      tokio::time::sleep(pause).await;
+    }
+  }
+}
+
+/// The host and port of a url, or the url itself when it cannot be parsed.
+fn host(url: &str) -> String {
+  match Url::parse(url) {
+    Ok(parsed) => match parsed.port_or_known_default() {
+      Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+      None => parsed.host_str().unwrap_or_default().to_string(),
+    },
+    Err(_) => url.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  async fn elapsed_for(limiter: &RateLimiter, urls: &[&str]) -> Duration {
+    let started = Instant::now();
+    for url in urls {
+      limiter.wait(url).await;
+    }
+    started.elapsed()
+  }
+
+  #[tokio::test]
+  async fn test_unlimited() {
+    let limiter = RateLimiter::new(0.0, 0.0, 1);
+
+    assert!(elapsed_for(&limiter, &["http://a.com"; 100]).await < Duration::from_millis(50));
+  }
+
+  #[tokio::test]
+  async fn test_global_rate_after_burst() {
+    let limiter = RateLimiter::new(20.0, 0.0, 2);
+
+    // two requests go through at once, the next two are 50ms apart
+    let elapsed = elapsed_for(&limiter, &["http://a.com", "http://b.com", "http://a.com", "http://b.com"]).await;
+    assert!(elapsed >= Duration::from_millis(95), "took {:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(300), "took {:?}", elapsed);
+  }
+
+  #[tokio::test]
+  async fn test_host_rate() {
+    let limiter = RateLimiter::new(0.0, 10.0, 1);
+
+    assert!(elapsed_for(&limiter, &["http://a.com/1", "http://b.com/1", "https://a.com/2"]).await < Duration::from_millis(50));
+    let elapsed = elapsed_for(&limiter, &["http://a.com/3"]).await;
+    assert!(elapsed >= Duration::from_millis(50), "took {:?}", elapsed);
+  }
+
+  #[tokio::test]
+  async fn test_jitter() {
+    let limiter = RateLimiter::new(0.0, 0.0, 1).with_jitter(Duration::from_millis(20));
+
+    assert!(elapsed_for(&limiter, &["http://a.com"; 5]).await < Duration::from_millis(150));
+  }
+
+  #[test]
+  fn test_seeded_jitter_is_reproducible() {
+    let pauses = |limiter: RateLimiter| (0..5).map(|_| limiter.jitter.next()).collect::<Vec<_>>();
+    let first = pauses(RateLimiter::new(0.0, 0.0, 1).with_jitter(Duration::from_millis(20)).with_jitter_seed(7));
+    let second = pauses(RateLimiter::new(0.0, 0.0, 1).with_jitter(Duration::from_millis(20)).with_jitter_seed(7));
+
+    assert_eq!(first, second);
+    assert!(first.iter().all(|pause| *pause < Duration::from_millis(20)));
+    assert!(first.iter().any(|pause| *pause != first[0]));
+  }
+
+  #[test]
+  fn test_host() {
+    assert_eq!(host("https://example.com/api?q=1"), "example.com:443");
+    assert_eq!(host("http://localhost:8080/"), "localhost:8080");
+  }
+}
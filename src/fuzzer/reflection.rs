@@ -0,0 +1,130 @@
+use percent_encoding::percent_decode_str;
+use reqwest::header::HeaderMap;
+
+/// Finds where a payload is echoed back, looking for it raw, html decoded and url decoded,
+/// e.g. `body (html decoded)` or `header x-echo`.
+pub fn find_reflections(payload: &str, headers: &HeaderMap, body: &str) -> Vec<String> {
+  if payload.is_empty() {
+    return Vec::new();
+  }
+
+  let mut locations = Vec::new();
+  if body.contains(payload) {
+    locations.push("body".to_string());
+  } else if html_decode(body).contains(payload) {
+    locations.push("body (html decoded)".to_string());
+  } else if url_decode(body).contains(payload) {
+    locations.push("body (url decoded)".to_string());
+  }
+
+  for (name, value) in headers {
+    let value = String::from_utf8_lossy(value.as_bytes());
+    if value.contains(payload) || html_decode(&value).contains(payload) || url_decode(&value).contains(payload) {
+      locations.push(format!("header {}", name));
+    }
+  }
+
+  locations
+}
+
+pub fn url_decode(value: &str) -> String {
+  percent_decode_str(&value.replace('+', " ")).decode_utf8_lossy().to_string()
+}
+
+/// Longest entity name looked at after a `&`, so bodies full of `&` are not searched to the end for each one.
+const ENTITY_LIMIT: usize = 10;
+
+/// Decodes the common named entities and all numeric character references.
+pub fn html_decode(value: &str) -> String {
+  let mut decoded = String::with_capacity(value.len());
+  let mut rest = value;
+
+  while let Some(start) = rest.find('&') {
+    decoded.push_str(&rest[..start]);
+    rest = &rest[start..];
+
+    let entity = rest[1..].bytes().take(ENTITY_LIMIT + 1).position(|byte| byte == b';')
+      .and_then(|end| decode_entity(&rest[1..end + 1]).map(|character| (character, end + 2)));
+
+    match entity {
+      Some((character, length)) => {
+        decoded.push(character);
+        rest = &rest[length..];
+      }
+      None => {
+        decoded.push('&');
+        rest = &rest[1..];
+      }
+    }
+  }
+
+  decoded.push_str(rest);
+  decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+  match entity {
+    "amp" => Some('&'),
+    "lt" => Some('<'),
+    "gt" => Some('>'),
+    "quot" => Some('"'),
+    "apos" => Some('\''),
+    "nbsp" => Some('\u{a0}'),
+    _ => {
+      let code = if let Some(hex) = entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+        u32::from_str_radix(hex, 16).ok()
+      } else if let Some(decimal) = entity.strip_prefix('#') {
+        decimal.parse().ok()
+      } else {
+        None
+      };
+
+      code.and_then(char::from_u32)
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use reqwest::header::HeaderValue;
+  use super::*;
+
+  #[test]
+  fn test_html_decode() {
+    assert_eq!(html_decode("&lt;script&gt;alert(&#39;x&#x27;)&lt;/script&gt;"), "<script>alert('x')</script>");
+    assert_eq!(html_decode("a & b &unknown; &amp"), "a & b &unknown; &amp");
+    assert_eq!(html_decode("&averyverylongname;"), "&averyverylongname;");
+  }
+
+  #[test]
+  fn test_url_decode() {
+    assert_eq!(url_decode("%3Cscript%3E+x"), "<script> x");
+  }
+
+  #[test]
+  fn test_find_reflections_in_body() {
+    let headers = HeaderMap::new();
+    let payload = "<script>alert(1)</script>";
+
+    assert_eq!(find_reflections(payload, &headers, "hi <script>alert(1)</script>"), vec!["body"]);
+    assert_eq!(find_reflections(payload, &headers, "hi &lt;script&gt;alert(1)&lt;/script&gt;"), vec!["body (html decoded)"]);
+    assert_eq!(find_reflections(payload, &headers, "hi %3Cscript%3Ealert(1)%3C/script%3E"), vec!["body (url decoded)"]);
+    assert!(find_reflections(payload, &headers, "hi").is_empty());
+  }
+
+  #[test]
+  fn test_find_reflections_in_headers() {
+    let mut headers = HeaderMap::new();
+    headers.insert("location", HeaderValue::from_static("/login?next=%2Fadmin"));
+
+    assert_eq!(find_reflections("/admin", &headers, ""), vec!["header location"]);
+
+    headers.insert("x-echo", HeaderValue::from_static("&lt;b&gt;"));
+    assert_eq!(find_reflections("<b>", &headers, ""), vec!["header x-echo"]);
+  }
+
+  #[test]
+  fn test_empty_payload_is_never_reflected() {
+    assert!(find_reflections("", &HeaderMap::new(), "anything").is_empty());
+  }
+}
@@ -3,34 +3,52 @@ use std::io::Write;
 use std::sync::Arc;
 use tokio::sync::mpsc::Receiver;
 use tokio::sync::RwLock;
+use crate::error::FuzzyError;
 use crate::fuzzer::fuzzy::FuzzingResult;
 use crate::fuzzer::metrics::Metrics;
+use crate::fuzzer::output::{OutputFormat, ResultRecord};
 
 pub struct ResultAggregator {
   metrics: Arc<RwLock<Metrics>>,
   receiver: Receiver<FuzzingResult>,
-  output_file: Option<File>
+  output_file: Option<File>,
+  output_path: String,
+  format: OutputFormat,
 }
 
 impl ResultAggregator {
-  pub async fn new(receiver: Receiver<FuzzingResult>, output_path: Option<&str>, metrics: Arc<RwLock<Metrics>>) -> Self {
-    let output_file = output_path.map(|path| File::create(path).expect("failed to create output file"));
+  pub async fn new(receiver: Receiver<FuzzingResult>, output_path: Option<&str>, metrics: Arc<RwLock<Metrics>>) -> Result<Self, FuzzyError> {
+    let output_file = output_path
+      .map(|path| File::create(path).map_err(|err| FuzzyError::io(path, err)))
+      .transpose()?;
 
-    Self {
+    Ok(Self {
       metrics,
       receiver,
       output_file,
-    }
+      output_path: output_path.unwrap_or_default().to_string(),
+      format: OutputFormat::default(),
+    })
+  }
+
+  pub fn with_format(mut self, format: OutputFormat) -> Self {
+    self.format = format;
+    self
   }
 
   pub fn metrics(&self) -> &Arc<RwLock<Metrics>> {
     &self.metrics
   }
 
-  pub async fn process_results(&mut self) {
+  /// Stops at the first output that cannot be written, which closes the channel for the fuzzer.
+  pub async fn process_results(&mut self) -> Result<(), FuzzyError> {
     while let Some(result) = self.receiver.recv().await {
+      if self.format == OutputFormat::Jsonl {
+        self.write_to_output(&ResultRecord::new(&result).to_json())?;
+      }
+
       match result {
-        FuzzingResult::Success(_url) => {
+        FuzzingResult::Success(_url, _response) => {
           let mut metrics = self.metrics.write().await;
           metrics.successful_requests += 1;
         },
@@ -39,56 +57,112 @@ impl ResultAggregator {
             let mut metrics = self.metrics.write().await;
             metrics.failed_requests += 1;
           }
-          self.write_to_output(failure.failure_to_string(url).as_str());
+          if self.format == OutputFormat::Text {
+            self.write_to_output(failure.failure_to_string(url).as_str())?;
+          }
+        },
+        FuzzingResult::BuildError(url, err) => {
+          {
+            let mut metrics = self.metrics.write().await;
+            metrics.build_errors += 1;
+          }
+          if self.format == OutputFormat::Text {
+            self.write_to_output(format!("request_id: {}, id: {}, url: {}, could not build request: {}", url.request_id(), url.id(), url.url(), err).as_str())?;
+          }
         }
       }
       let mut metrics = self.metrics.write().await;
       metrics.total_requests += 1;
     }
+
+    Ok(())
   }
 
-  fn write_to_output(&mut self, data: &str) {
+  fn write_to_output(&mut self, data: &str) -> Result<(), FuzzyError> {
     if let Some(file) = &mut self.output_file {
-      writeln!(file, "{}", data).expect("failed to write to file");
+      writeln!(file, "{}", data).map_err(|err| FuzzyError::io(&self.output_path, err))?;
     }
+
+    Ok(())
   }
 }
 
+#[cfg(test)]
 mod tests {
   use super::*;
 
+  use std::time::Duration;
+  use reqwest::header::HeaderMap;
   use tokio::sync::mpsc;
-  use crate::fuzzer::fuzzy::{FuzzingResult, FuzzingFailure, FuzzedUrl};
+  use crate::error::RequestError;
+  use crate::fuzzer::fuzzy::{CapturedResponse, FuzzingResult, FuzzingFailure, FuzzedUrl};
 
   #[tokio::test]
   async fn test_result_aggregator_initialization() {
     let (_tx, rx) = mpsc::channel(32);
     let metrics = Metrics::new();
-    let aggregator = ResultAggregator::new(rx, None, metrics.clone()).await;
+    let aggregator = ResultAggregator::new(rx, None, metrics.clone()).await.unwrap();
 
     assert_eq!(aggregator.metrics().read().await.successful_requests, 0);
   }
 
+  #[tokio::test]
+  async fn test_result_aggregator_output_error() {
+    let (_tx, rx) = mpsc::channel(32);
+    let result = ResultAggregator::new(rx, Some("missing-dir/output.txt"), Metrics::new()).await;
+
+    assert!(matches!(result, Err(FuzzyError::Io { path, .. }) if path == "missing-dir/output.txt"));
+  }
+
   #[tokio::test]
   async fn test_result_aggregator_process_results() {
     let (tx, rx) = mpsc::channel(32);
     let metrics = Metrics::new();
-    let mut aggregator = ResultAggregator::new(rx, None, metrics.clone()).await;
+    let mut aggregator = ResultAggregator::new(rx, None, metrics.clone()).await.unwrap();
 
     tx.send(sample_fuzzing_success_result()).await.unwrap();
     tx.send(sample_fuzzing_failure_result()).await.unwrap();
+    tx.send(sample_fuzzing_build_error_result()).await.unwrap();
 
     drop(tx);
-    aggregator.process_results().await;  // Ideally, you'd want this to run in parallel or ensure all messages are processed.
+    aggregator.process_results().await.unwrap();  // Ideally, you'd want this to run in parallel or ensure all messages are processed.
 
     let m = aggregator.metrics().read().await;
     assert_eq!(m.successful_requests, 1);
     assert_eq!(m.failed_requests, 1);
-    assert_eq!(m.total_requests, 2);
+    assert_eq!(m.build_errors, 1);
+    assert_eq!(m.total_requests, 3);
+  }
+
+  #[tokio::test]
+  async fn test_result_aggregator_writes_jsonl() {
+    let output_path = std::env::temp_dir().join(format!("fuzzy-{}.jsonl", uuid::Uuid::new_v4()));
+    let (tx, rx) = mpsc::channel(32);
+    let metrics = Metrics::new();
+    let mut aggregator = ResultAggregator::new(rx, output_path.to_str(), metrics.clone()).await.unwrap()
+      .with_format(OutputFormat::Jsonl);
+
+    tx.send(sample_fuzzing_success_result()).await.unwrap();
+    tx.send(sample_fuzzing_failure_result()).await.unwrap();
+    tx.send(sample_fuzzing_build_error_result()).await.unwrap();
+
+    drop(tx);
+    aggregator.process_results().await.unwrap();
+    drop(aggregator);
+
+    let output = std::fs::read_to_string(&output_path).unwrap();
+    std::fs::remove_file(&output_path).unwrap();
+    let outcomes: Vec<String> = output.lines()
+      .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["outcome"].as_str().unwrap().to_string())
+      .collect();
+    assert_eq!(outcomes, vec!["success", "failure", "build_error"]);
   }
 
   fn sample_fuzzing_success_result() -> FuzzingResult {
-    FuzzingResult::Success(FuzzedUrl::new("http://test.com/test".parse().unwrap(), "test".parse().unwrap(), "test".parse().unwrap()))
+    FuzzingResult::Success(
+      FuzzedUrl::new("http://test.com/test".parse().unwrap(), "test".parse().unwrap(), "test".parse().unwrap()),
+      CapturedResponse::new(200, HeaderMap::new(), "ok".into(), Duration::from_millis(5))
+    )
   }
 
   fn sample_fuzzing_failure_result() -> FuzzingResult {
@@ -97,4 +171,11 @@ mod tests {
       FuzzingFailure::new(Option::None, Option::None, Option::None)
     )
   }
+
+  fn sample_fuzzing_build_error_result() -> FuzzingResult {
+    FuzzingResult::BuildError(
+      FuzzedUrl::new("http://test.com/test".parse().unwrap(), "test".parse().unwrap(), "test".parse().unwrap()),
+      RequestError::InvalidHeaderValue { name: "X-Test".into(), value: "a\nb".into() }
+    )
+  }
 }
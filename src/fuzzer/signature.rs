@@ -0,0 +1,116 @@
+use regex::Regex;
+use crate::domain::dictionary::ErrorSignature;
+use crate::error::FuzzyError;
+use crate::fuzzer::fuzzy::FailureReason;
+
+/// Matched text longer than this is cut in the reported reason.
+const MATCH_LIMIT: usize = 120;
+
+/// The compiled patterns of the error signatures every response body is searched for.
+#[derive(Debug, Default)]
+pub struct SignatureScanner {
+  signatures: Vec<(String, Vec<Regex>)>,
+}
+
+impl SignatureScanner {
+  /// A signature with the same id as an earlier one replaces it, so a dictionary file can redefine a shipped one.
+  pub fn new(signatures: &[ErrorSignature]) -> Result<Self, FuzzyError> {
+    let mut compiled: Vec<(String, Vec<Regex>)> = Vec::new();
+
+    for signature in signatures {
+      let patterns = signature.patterns()
+        .iter()
+        .map(|pattern| Regex::new(pattern))
+        .collect::<Result<Vec<Regex>, regex::Error>>()
+        .map_err(|source| FuzzyError::InvalidSignature { id: signature.id().clone(), source })?;
+
+      match compiled.iter_mut().find(|(id, _)| id == signature.id()) {
+        Some(existing) => existing.1 = patterns,
+        None => compiled.push((signature.id().clone(), patterns)),
+      }
+    }
+
+    Ok(Self { signatures: compiled })
+  }
+
+  /// Ids of the signatures found in the body.
+  pub fn matching_ids(&self, body: &str) -> Vec<String> {
+    self.scan(body).into_iter().map(|(id, _)| id).collect()
+  }
+
+  /// One reason per signature found in the body, with the first text matching it, skipping the ids in `ignored`.
+  pub fn check(&self, body: &str, ignored: &[String]) -> Vec<FailureReason> {
+    self.scan(body)
+      .into_iter()
+      .filter(|(id, _)| !ignored.contains(id))
+      .map(|(id, matched)| FailureReason::Signature { id, matched })
+      .collect()
+  }
+
+  fn scan(&self, body: &str) -> Vec<(String, String)> {
+    self.signatures
+      .iter()
+      .filter_map(|(id, patterns)| {
+        patterns.iter()
+          .find_map(|pattern| pattern.find(body))
+          .map(|found| (id.clone(), shorten(found.as_str())))
+      })
+      .collect()
+  }
+}
+
+/// Cuts the text to at most [`MATCH_LIMIT`] characters.
+fn shorten(text: &str) -> String {
+  match text.char_indices().nth(MATCH_LIMIT) {
+    Some((end, _)) => format!("{}...", &text[..end]),
+    None => text.to_string(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn signature(id: &str, patterns: &[&str]) -> ErrorSignature {
+    ErrorSignature::new(id.into(), "test".into(), patterns.iter().map(|pattern| pattern.to_string()).collect())
+  }
+
+  #[test]
+  fn test_check() {
+    let scanner = SignatureScanner::new(&[
+      signature("mysqlError", &["You have an error in your SQL syntax", r"mysql_fetch_\w+"]),
+      signature("unixPasswdFile", &[r"root:[x*]?:0:0:"]),
+    ]).unwrap();
+
+    let reasons = scanner.check("<b>Warning</b>: mysql_fetch_array() expects parameter 1", &[]);
+    assert_eq!(reasons, vec![FailureReason::Signature { id: "mysqlError".into(), matched: "mysql_fetch_array".into() }]);
+    assert_eq!(scanner.matching_ids("root:x:0:0:root:/root:/bin/bash"), vec!["unixPasswdFile"]);
+    assert!(scanner.check("root:x:0:0:root:/root:/bin/bash", &["unixPasswdFile".into()]).is_empty());
+    assert!(scanner.check("{\"id\": 1}", &[]).is_empty());
+  }
+
+  #[test]
+  fn test_later_signatures_replace_earlier_ones() {
+    let scanner = SignatureScanner::new(&[signature("stackTrace", &["Traceback"]), signature("stackTrace", &["panicked at"])]).unwrap();
+
+    assert!(scanner.check("Traceback (most recent call last):", &[]).is_empty());
+    assert_eq!(scanner.matching_ids("thread 'main' panicked at src/main.rs:2:5"), vec!["stackTrace"]);
+  }
+
+  #[test]
+  fn test_invalid_pattern() {
+    let result = SignatureScanner::new(&[signature("broken", &["(unclosed"])]);
+
+    assert!(matches!(result, Err(FuzzyError::InvalidSignature { id, .. }) if id == "broken"));
+  }
+
+  #[test]
+  fn test_long_matches_are_shortened() {
+    let scanner = SignatureScanner::new(&[signature("trace", &[r"at .*"])]).unwrap();
+
+    match &scanner.check(&format!("at {}", "é".repeat(200)), &[])[0] {
+      FailureReason::Signature { matched, .. } => assert_eq!(matched.chars().count(), MATCH_LIMIT + 3),
+      reason => panic!("expected a signature, got {:?}", reason),
+    }
+  }
+}
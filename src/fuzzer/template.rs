@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use url::Url;
+use crate::domain::input::{HttpMethod, Path, PathEncoding};
+use crate::domain::placeholder::{placeholder_names, replace_placeholders};
+use crate::fuzzer::body::{render_body, BodyFormat};
+use crate::fuzzer::fuzzy::FuzzedUrl;
+
+/// A request of a path with its placeholders still in place, each location knowing how its values are encoded.
+#[derive(Clone, Debug)]
+pub struct RequestTemplate {
+  method: HttpMethod,
+  url_path: String,
+  query: String,
+  headers: Vec<TemplateHeader>,
+  body: String,
+  body_format: BodyFormat,
+  path_encoding: PathEncoding,
+}
+
+#[derive(Clone, Debug)]
+struct TemplateHeader {
+  name: String,
+  value: String,
+  fuzz: bool,
+}
+
+impl RequestTemplate {
+  pub fn new(url: &Url, path: &Path) -> Self {
+    let url = url.to_string();
+    let (url_path, query) = match url.find('?') {
+      Some(index) => url.split_at(index),
+      None => (url.as_str(), ""),
+    };
+
+    let headers = path.headers()
+      .iter()
+      .map(|header| TemplateHeader {
+        name: header.name().clone(),
+        value: header.template().to_string(),
+        fuzz: header.fuzz(),
+      })
+      .collect();
+
+    Self {
+      method: path.method().clone(),
+      url_path: url_path.to_string(),
+      query: query.to_string(),
+      headers,
+      body: path.body().clone(),
+      body_format: BodyFormat::from_content_type(path.content_type()),
+      path_encoding: path.path_encoding().clone(),
+    }
+  }
+
+  /// Names of every placeholder in the request, in order of first appearance.
+  pub fn placeholders(&self) -> Vec<String> {
+    let fuzzed_headers = self.headers
+      .iter()
+      .filter(|header| header.fuzz)
+      .map(|header| header.value.as_str());
+
+    let mut names: Vec<String> = Vec::new();
+    for text in [self.url_path.as_str(), self.query.as_str()].into_iter().chain(fuzzed_headers).chain([self.body.as_str()]) {
+      for name in placeholder_names(text) {
+        if !names.contains(&name) {
+          names.push(name);
+        }
+      }
+    }
+
+    names
+  }
+
+  /// Placeholders in the path are encoded as configured by the path, query and header placeholders take the value
+  /// as is and the body escapes values for its content type.
+  pub fn render(&self, values: &HashMap<String, String>) -> FuzzedUrl {
+    let url = format!(
+      "{}{}",
+      replace_placeholders(&self.url_path, values, |value| self.path_encoding.encode(value)),
+      replace_placeholders(&self.query, values, |value| value.to_string())
+    );
+
+    let headers = self.headers
+      .iter()
+      .map(|header| {
+        let value = if header.fuzz {
+          replace_placeholders(&header.value, values, |value| value.to_string())
+        } else {
+          header.value.clone()
+        };
+
+        (header.name.clone(), value)
+      })
+      .collect();
+
+    FuzzedUrl::new(url, String::new(), String::new())
+      .with_method(self.method.clone())
+      .with_headers(headers)
+      .with_body(render_body(&self.body, self.body_format, values))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::domain::input::{HeaderParameter, HttpMethod, QueryParameter};
+  use super::*;
+
+  fn sample_path() -> Path {
+    Path::new(
+      "/users/{fuzz_id}/orders".into(),
+      HttpMethod::POST,
+      200,
+      vec![],
+      "".into(),
+      vec![QueryParameter::new("q".into(), true, None)],
+      vec![
+        HeaderParameter::new("Content-Type".into(), Some("application/json".into()), false),
+        HeaderParameter::new("Authorization".into(), Some("Bearer {fuzz_token}".into()), true),
+        HeaderParameter::new("Accept".into(), Some("{fuzz}".into()), false),
+      ],
+      r#"{ "name": "{fuzz_id}" }"#.into()
+    )
+  }
+
+  fn sample_template(path: &Path) -> RequestTemplate {
+    let url = path.to_url("https://example.com", "/api").unwrap();
+    RequestTemplate::new(&url, path)
+  }
+
+  #[test]
+  fn test_placeholders() {
+    let template = sample_template(&sample_path());
+    assert_eq!(template.placeholders(), vec!["fuzz_id", "fuzz", "fuzz_token"]);
+  }
+
+  #[test]
+  fn test_render() {
+    let template = sample_template(&sample_path());
+    let values = HashMap::from([
+      ("fuzz_id".to_string(), "a/\"b".to_string()),
+      ("fuzz".to_string(), "a/b".to_string()),
+      ("fuzz_token".to_string(), "t".to_string()),
+    ]);
+
+    let rendered = template.render(&values);
+    assert_eq!(rendered.method(), &HttpMethod::POST);
+    assert_eq!(rendered.url(), "https://example.com/api/users/a%2F%22b/orders?q=a/b");
+    assert_eq!(rendered.headers(), &vec![
+      ("Content-Type".to_string(), "application/json".to_string()),
+      ("Authorization".to_string(), "Bearer t".to_string()),
+      ("Accept".to_string(), "{fuzz}".to_string()),
+    ]);
+    assert_eq!(rendered.body(), r#"{ "name": "a/\"b" }"#);
+  }
+
+  #[test]
+  fn test_render_encodes_path_segments() {
+    let values = HashMap::from([("fuzz_id".to_string(), "a/b".to_string())]);
+    let cases = vec![
+      (PathEncoding::Raw, "https://example.com/api/users/a/b/orders?q=%7Bfuzz%7D"),
+      (PathEncoding::Percent, "https://example.com/api/users/a%2Fb/orders?q=%7Bfuzz%7D"),
+      (PathEncoding::Double, "https://example.com/api/users/a%252Fb/orders?q=%7Bfuzz%7D"),
+    ];
+
+    for (encoding, expected) in cases {
+      let template = sample_template(&sample_path().with_path_encoding(encoding));
+      assert_eq!(template.render(&values).url(), expected);
+    }
+  }
+}
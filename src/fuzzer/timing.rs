@@ -0,0 +1,92 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+use regex::{Captures, Regex};
+
+/// A response is only delayed when it took at least this much longer than the baseline median, whatever the factor.
+pub const MIN_DELAY: Duration = Duration::from_secs(1);
+
+/// The delay of a confirmation request is this many times the original one.
+const CONFIRMATION_SCALE: u64 = 2;
+
+/// The extra latency of a confirmation request has to be at least this many times the original extra latency.
+const CONFIRMATION_RATIO: f64 = 1.5;
+
+/// Matches the delay of sleep style payloads, e.g. `SLEEP(5)`, `pg_sleep(5)`, `WAITFOR DELAY '0:0:5'`,
+/// `BENCHMARK(5000000, ...)`, `DBMS_PIPE.RECEIVE_MESSAGE('a', 5)`, `sleep 5` or `ping -c 5`.
+fn delay_regex() -> &'static Regex {
+  static DELAY: OnceLock<Regex> = OnceLock::new();
+  DELAY.get_or_init(|| {
+    Regex::new(r"(?i)(sleep\s*\(\s*|sleep\s+(?:-s\s+)?|waitfor\s+delay\s+'\d+:\d+:|benchmark\s*\(\s*|receive_message\s*\(\s*'[^']*'\s*,\s*|ping\s+-[cn]\s+|timeout\s+(?:/t\s+)?)(\d+)")
+      .expect("delay pattern is valid")
+  })
+}
+
+/// Whether a response took `factor` times the baseline median latency, and at least [`MIN_DELAY`] longer.
+pub fn is_delayed(latency: Duration, median: Duration, factor: f64) -> bool {
+  factor > 0.0 && latency.as_secs_f64() >= median.as_secs_f64() * factor && latency >= median + MIN_DELAY
+}
+
+/// Returns the payload with every non-zero delay scaled for a confirmation request, `None` when it has none.
+///
+/// Zero delays are kept, so a conditional payload such as `IF (1=1) THEN sleep(3) ELSE sleep(0)` keeps its shape.
+pub fn confirmation_payload(payload: &str) -> Option<String> {
+  let mut scaled = false;
+  let confirmation = delay_regex().replace_all(payload, |captures: &Captures| {
+    let delay: u64 = captures[2].parse().unwrap_or_default();
+    if delay > 0 {
+      scaled = true;
+    }
+    format!("{}{}", &captures[1], delay.saturating_mul(CONFIRMATION_SCALE))
+  });
+
+  if scaled { Some(confirmation.into_owned()) } else { None }
+}
+
+/// Whether the extra latency of the confirmation request grew along with its delay.
+pub fn is_confirmed(latency: Duration, confirmation_latency: Duration, median: Duration) -> bool {
+  let extra = latency.saturating_sub(median).as_secs_f64();
+  let confirmation_extra = confirmation_latency.saturating_sub(median).as_secs_f64();
+
+  confirmation_extra >= extra * CONFIRMATION_RATIO
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_confirmation_payload() {
+    assert_eq!(confirmation_payload("1' AND SLEEP(5)-- -").unwrap(), "1' AND SLEEP(10)-- -");
+    assert_eq!(confirmation_payload("'; SELECT pg_sleep( 5 )--").unwrap(), "'; SELECT pg_sleep( 10 )--");
+    assert_eq!(confirmation_payload("'; WAITFOR DELAY '0:0:5'--").unwrap(), "'; WAITFOR DELAY '0:0:10'--");
+    assert_eq!(confirmation_payload("1 AND BENCHMARK(5000000,MD5(1))").unwrap(), "1 AND BENCHMARK(10000000,MD5(1))");
+    assert_eq!(confirmation_payload("1' AND 1=DBMS_PIPE.RECEIVE_MESSAGE('a',5)-- -").unwrap(), "1' AND 1=DBMS_PIPE.RECEIVE_MESSAGE('a',10)-- -");
+    assert_eq!(confirmation_payload("; sleep 5 #").unwrap(), "; sleep 10 #");
+    assert_eq!(confirmation_payload("$(sleep 5)").unwrap(), "$(sleep 10)");
+    assert_eq!(confirmation_payload("| ping -c 5 127.0.0.1").unwrap(), "| ping -c 10 127.0.0.1");
+    assert_eq!(
+      confirmation_payload("IF (1=1) THEN dbms_lock.sleep(3); ELSE dbms_lock.sleep(0); END IF; END;").unwrap(),
+      "IF (1=1) THEN dbms_lock.sleep(6); ELSE dbms_lock.sleep(0); END IF; END;"
+    );
+    assert_eq!(confirmation_payload("' OR 1=1 -- 1"), None);
+    assert_eq!(confirmation_payload("sleep(0)"), None);
+  }
+
+  #[test]
+  fn test_is_delayed() {
+    let median = Duration::from_millis(100);
+
+    assert!(is_delayed(Duration::from_millis(5200), median, 5.0));
+    assert!(!is_delayed(Duration::from_millis(400), median, 2.0));
+    assert!(!is_delayed(Duration::from_millis(1500), Duration::from_millis(1000), 1.2));
+    assert!(!is_delayed(Duration::from_secs(10), median, 0.0));
+  }
+
+  #[test]
+  fn test_is_confirmed() {
+    let median = Duration::from_millis(100);
+
+    assert!(is_confirmed(Duration::from_millis(5100), Duration::from_millis(10100), median));
+    assert!(!is_confirmed(Duration::from_millis(5100), Duration::from_millis(5200), median));
+  }
+}
@@ -0,0 +1,216 @@
+use url::form_urlencoded;
+use crate::domain::input::{FuzzyInput, HttpMethod};
+use crate::error::FuzzyError;
+use crate::import::parse_method;
+use crate::import::request::{MarkedRequest, MARKER};
+
+/// Options whose value is not part of the request, e.g. `-o out.html`.
+const IGNORED_WITH_VALUE: [&str; 12] = [
+  "-o", "--output", "-m", "--max-time", "--connect-timeout", "-x", "--proxy", "--retry", "-w", "--write-out", "--cacert", "--cert",
+];
+
+/// Generates an input from a curl command, e.g. copied with "copy as cURL" and with fuzz positions marked as
+/// `§value§`.
+///
+/// The method, url, headers, cookies and data options are imported. Data is sent as a form unless a content type
+/// header says otherwise, and appended to the query with `-G`.
+pub fn import(file: &str, content: &str) -> Result<FuzzyInput, FuzzyError> {
+  parse(file, content)?.to_input(file)
+}
+
+fn parse(file: &str, content: &str) -> Result<MarkedRequest, FuzzyError> {
+  let invalid = |message: String| FuzzyError::Import { path: file.to_string(), message };
+
+  let words = shell_words::split(content.trim()).map_err(|err| invalid(format!("invalid command: {}", err)))?;
+  let mut words = words.into_iter().skip_while(|word| word == "curl");
+
+  let mut method: Option<HttpMethod> = None;
+  let mut url: Option<String> = None;
+  let mut headers: Vec<(String, String)> = Vec::new();
+  let mut data: Vec<String> = Vec::new();
+  let mut json = false;
+  let mut get = false;
+
+  while let Some(word) = words.next() {
+    if !word.starts_with('-') || word == "-" {
+      url = Some(word);
+      continue;
+    }
+
+    // values can be attached, e.g. `-XPOST` or `--request=POST`
+    let (option, attached) = match word.split_once('=') {
+      Some((option, value)) if word.starts_with("--") => (option.to_string(), Some(value.to_string())),
+      _ if !word.starts_with("--") && word.len() > 2 && word.is_char_boundary(2) => (word[..2].to_string(), Some(word[2..].to_string())),
+      _ => (word.clone(), None),
+    };
+    let mut value = || attached.clone().or_else(|| words.next()).ok_or_else(|| invalid(format!("missing value for {}", option)));
+
+    match option.as_str() {
+      "-X" | "--request" => {
+        let name = value()?;
+        method = Some(parse_method(&name).ok_or_else(|| invalid(format!("unsupported method {}", name)))?);
+      }
+      "-H" | "--header" => {
+        let header = value()?;
+        match header.split_once(':') {
+          Some((name, value)) => headers.push((name.trim().to_string(), value.trim().to_string())),
+          None => log::warn!("ignoring header without a value: {}", header),
+        }
+      }
+      "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => data.push(value()?),
+      "--data-urlencode" => {
+        let pair = value()?;
+        data.push(match pair.split_once('=') {
+          Some((name, value)) => format!("{}={}", name, urlencode_marked(value)),
+          None => urlencode_marked(&pair),
+        });
+      }
+      "--json" => {
+        data.push(value()?);
+        json = true;
+      }
+      "-b" | "--cookie" => headers.push(("Cookie".into(), value()?)),
+      "-A" | "--user-agent" => headers.push(("User-Agent".into(), value()?)),
+      "-e" | "--referer" => headers.push(("Referer".into(), value()?)),
+      "--url" => url = Some(value()?),
+      "-G" | "--get" => get = true,
+      "-I" | "--head" => method = Some(HttpMethod::HEAD),
+      "-u" | "--user" => {
+        value()?;
+        log::warn!("{} is not imported, add an Authorization header instead", option);
+      }
+      option if IGNORED_WITH_VALUE.contains(&option) => {
+        value()?;
+      }
+      // flags such as -k, -L, -s, --compressed or combined ones like -sSL
+      _ => log::debug!("ignoring curl option {}", word),
+    }
+  }
+
+  let mut url = url.ok_or_else(|| invalid("no url in command".into()))?;
+  let mut body = data.join("&");
+
+  if get && !body.is_empty() {
+    url = format!("{}{}{}", url, if url.contains('?') { '&' } else { '?' }, body);
+    body.clear();
+  }
+
+  if json {
+    if !has_header(&headers, "Content-Type") {
+      headers.push(("Content-Type".into(), "application/json".into()));
+    }
+    if !has_header(&headers, "Accept") {
+      headers.push(("Accept".into(), "application/json".into()));
+    }
+  } else if !body.is_empty() && !has_header(&headers, "Content-Type") {
+    headers.push(("Content-Type".into(), "application/x-www-form-urlencoded".into()));
+  }
+
+  let method = method.unwrap_or(if body.is_empty() { HttpMethod::GET } else { HttpMethod::POST });
+
+  Ok(MarkedRequest::new(method, url, headers, body))
+}
+
+/// Url encodes the text outside of `§value§` markers, so they still mark the fuzz positions. Marked values are kept as
+/// they are, since placeholder values are encoded when the form body is rendered.
+fn urlencode_marked(text: &str) -> String {
+  let encode = |literal: &str| form_urlencoded::byte_serialize(literal.as_bytes()).collect::<String>();
+  let mut encoded = String::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find(MARKER) {
+    let after_start = &rest[start + MARKER.len_utf8()..];
+    match after_start.find(MARKER) {
+      Some(end) => {
+        encoded.push_str(&encode(&rest[..start]));
+        encoded.push_str(&rest[start..start + MARKER.len_utf8() * 2 + end]);
+        rest = &after_start[end + MARKER.len_utf8()..];
+      }
+      None => break,
+    }
+  }
+
+  encoded.push_str(&encode(rest));
+  encoded
+}
+
+fn has_header(headers: &[(String, String)], name: &str) -> bool {
+  headers.iter().any(|(header, _)| header.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_parse_copied_command() {
+    let command = r#"curl 'https://api.test.com/users?id=§1§' \
+      -H 'Authorization: Bearer §token§' \
+      -H 'content-type: application/json' \
+      --data-raw '{"name":"§bob§"}' \
+      --compressed -sSL"#;
+
+    let request = parse("command.txt", command).unwrap();
+    assert_eq!(request.method(), &HttpMethod::POST);
+    assert_eq!(request.url(), "https://api.test.com/users?id=§1§");
+    assert_eq!(request.headers(), &vec![
+      ("Authorization".to_string(), "Bearer §token§".to_string()),
+      ("content-type".to_string(), "application/json".to_string()),
+    ]);
+    assert_eq!(request.body(), r#"{"name":"§bob§"}"#);
+  }
+
+  #[test]
+  fn test_parse_options() {
+    let command = "curl -XPUT --url=http://test.com/a -b 'session=§abc§' -A fuzzy -d a=1 -d b=§2§ -o /dev/null";
+    let request = parse("command.txt", command).unwrap();
+
+    assert_eq!(request.method(), &HttpMethod::PUT);
+    assert_eq!(request.url(), "http://test.com/a");
+    assert_eq!(request.body(), "a=1&b=§2§");
+    assert_eq!(request.headers(), &vec![
+      ("Cookie".to_string(), "session=§abc§".to_string()),
+      ("User-Agent".to_string(), "fuzzy".to_string()),
+      ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
+    ]);
+  }
+
+  #[test]
+  fn test_parse_get_data_and_json() {
+    let request = parse("command.txt", "curl -G http://test.com/search?x=1 --data-urlencode 'q=§a b§'").unwrap();
+    assert_eq!(request.method(), &HttpMethod::GET);
+    assert_eq!(request.url(), "http://test.com/search?x=1&q=§a b§");
+    assert_eq!(request.body(), "");
+
+    let request = parse("command.txt", r#"curl http://test.com --json '{"a":1}'"#).unwrap();
+    assert_eq!(request.method(), &HttpMethod::POST);
+    assert_eq!(request.headers()[0], ("Content-Type".to_string(), "application/json".to_string()));
+  }
+
+  #[test]
+  fn test_import_urlencoded_data_keeps_markers() {
+    let input = import("command.txt", "curl http://test.com/login --data-urlencode 'q=§a b§' --data-urlencode 'note=x&y §1'").unwrap();
+
+    let path = &input.paths()[0];
+    assert_eq!(path.method(), &HttpMethod::POST);
+    assert_eq!(path.body(), "q={fuzz_q}&note=x%26y+%C2%A71");
+    assert_eq!(path.fuzz_points().len(), 1);
+    assert_eq!(path.fuzz_point("fuzz_q").unwrap().default(), "a b");
+  }
+
+  #[test]
+  fn test_import() {
+    let input = import("command.txt", "curl 'http://test.com/users?id=§1§' -H 'X-Token: §t§'").unwrap();
+
+    let path = &input.paths()[0];
+    assert_eq!(path.query_parameters()[0].template(), "{fuzz_id}");
+    assert_eq!(path.headers()[0].template(), "{fuzz_X_Token}");
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert!(matches!(parse("command.txt", "curl -H 'Accept: */*'"), Err(FuzzyError::Import { .. })));
+    assert!(matches!(parse("command.txt", "curl 'unterminated"), Err(FuzzyError::Import { .. })));
+    assert!(matches!(parse("command.txt", "curl -X TRACE http://test.com"), Err(FuzzyError::Import { .. })));
+  }
+}
@@ -0,0 +1,333 @@
+use std::collections::HashSet;
+use serde::Deserialize;
+use url::Url;
+use crate::domain::input::{AttackStrategy, FuzzyInput, HeaderParameter, HttpMethod, Path, QueryParameter};
+use crate::error::FuzzyError;
+use crate::import::{json_template, parse_method, split_server_url, FuzzPoints};
+
+/// Headers set by the client or connection rather than the application, never imported.
+const SKIPPED_HEADERS: [&str; 6] = ["host", "content-length", "connection", "cookie", "accept-encoding", "transfer-encoding"];
+
+/// Which entries of a capture are imported, every entry when a list is empty.
+#[derive(Clone, Debug, Default)]
+pub struct HarFilter {
+  /// Hosts to keep, a host also matches its subdomains.
+  pub hosts: Vec<String>,
+  /// Response content types to keep, matched as case insensitive substrings, e.g. `json`.
+  pub content_types: Vec<String>,
+}
+
+impl HarFilter {
+  fn matches(&self, url: &Url, entry: &Entry) -> bool {
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    let host_matches = self.hosts.is_empty() || self.hosts.iter().any(|filter| {
+      let filter = filter.to_ascii_lowercase();
+      host == filter || host.ends_with(&format!(".{}", filter))
+    });
+
+    let content_type = entry.response.content.mime_type.to_ascii_lowercase();
+    let content_type_matches = self.content_types.is_empty() || self.content_types.iter()
+      .any(|filter| content_type.contains(&filter.to_ascii_lowercase()));
+
+    host_matches && content_type_matches
+  }
+}
+
+#[derive(Debug, Deserialize)]
+struct Har {
+  log: Log,
+}
+
+#[derive(Debug, Deserialize)]
+struct Log {
+  entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entry {
+  request: Request,
+  response: Response,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Request {
+  method: String,
+  url: String,
+  #[serde(default)]
+  headers: Vec<NameValue>,
+  #[serde(default)]
+  cookies: Vec<NameValue>,
+  post_data: Option<PostData>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+  status: u16,
+  #[serde(default)]
+  content: Content,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Content {
+  #[serde(default)]
+  mime_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PostData {
+  #[serde(default)]
+  mime_type: String,
+  #[serde(default)]
+  text: String,
+  #[serde(default)]
+  params: Vec<NameValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NameValue {
+  name: String,
+  #[serde(default)]
+  value: String,
+}
+
+/// Generates an input with one path per captured request, skipping repeats of the same method, path and query
+/// parameter names.
+///
+/// Query parameters, headers, cookies and body fields all become fuzz points defaulting to their recorded value, and
+/// the recorded response status becomes `expected_status`. An input has a single host, so entries on other hosts
+/// than the first one kept are skipped.
+pub fn import(file: &str, content: &str, filter: &HarFilter) -> Result<FuzzyInput, FuzzyError> {
+  let har: Har = serde_json::from_str(content)
+    .map_err(|err| FuzzyError::Import { path: file.to_string(), message: format!("invalid har: {}", err) })?;
+
+  let mut host: Option<String> = None;
+  let mut seen = HashSet::new();
+  let mut paths = Vec::new();
+
+  for entry in &har.log.entries {
+    let url = match Url::parse(&entry.request.url) {
+      Ok(url) => url,
+      Err(err) => {
+        log::warn!("skipping {}: {}", entry.request.url, err);
+        continue;
+      }
+    };
+
+    if !filter.matches(&url, entry) {
+      continue;
+    }
+
+    let method = match parse_method(&entry.request.method) {
+      Some(method) => method,
+      None => {
+        log::debug!("skipping {} request to {}", entry.request.method, url);
+        continue;
+      }
+    };
+
+    let (entry_host, _) = split_server_url(&url);
+    if host.get_or_insert_with(|| entry_host.clone()) != &entry_host {
+      log::warn!("skipping {} since the input is for {}, filter by host to import it", url, host.as_deref().unwrap_or_default());
+      continue;
+    }
+
+    let query_names: Vec<String> = url.query_pairs().map(|(name, _)| name.into_owned()).collect();
+    if !seen.insert(format!("{:?} {} {:?}", method, url.path(), query_names)) {
+      continue;
+    }
+
+    paths.push(to_path(&url, method, entry));
+  }
+
+  match host {
+    Some(host) => Ok(FuzzyInput::new(host, "".into(), paths)),
+    None => Err(FuzzyError::Import { path: file.to_string(), message: "no entries left to import".into() }),
+  }
+}
+
+fn to_path(url: &Url, method: HttpMethod, entry: &Entry) -> Path {
+  let request = &entry.request;
+  let mut points = FuzzPoints::default();
+
+  let query_parameters = url.query_pairs()
+    .map(|(name, value)| QueryParameter::new(name.to_string(), true, Some(points.add(&name, &value))))
+    .collect();
+
+  let mut headers: Vec<HeaderParameter> = request.headers.iter()
+    .filter(|header| !header.name.starts_with(':') && !SKIPPED_HEADERS.contains(&header.name.to_ascii_lowercase().as_str()))
+    .map(|header| {
+      // the content type decides how body placeholders are escaped, so it is sent as recorded
+      if header.name.eq_ignore_ascii_case("content-type") {
+        HeaderParameter::new(header.name.clone(), Some(header.value.clone()), false)
+      } else {
+        HeaderParameter::new(header.name.clone(), Some(points.add(&header.name, &header.value)), true)
+      }
+    })
+    .collect();
+
+  if !request.cookies.is_empty() {
+    let cookie = request.cookies.iter()
+      .map(|cookie| format!("{}={}", cookie.name, points.add(&format!("cookie_{}", cookie.name), &cookie.value)))
+      .collect::<Vec<_>>()
+      .join("; ");
+    headers.push(HeaderParameter::new("Cookie".into(), Some(cookie), true));
+  }
+
+  let body = request.post_data.as_ref()
+    .map(|post_data| body_template(post_data, &mut points))
+    .unwrap_or_default();
+
+  let status = match entry.response.status {
+    // aborted or blocked requests are recorded with a 0 status
+    0 => 200,
+    status => status,
+  };
+
+  Path::new(url.path().to_string(), method, status, vec![], "".into(), query_parameters, headers, body)
+    .with_attack(AttackStrategy::Sniper, points.into_points())
+}
+
+fn body_template(post_data: &PostData, points: &mut FuzzPoints) -> String {
+  let mime_type = post_data.mime_type.to_ascii_lowercase();
+
+  if mime_type.contains("json") {
+    if let Ok(recorded) = serde_json::from_str::<serde_json::Value>(&post_data.text) {
+      let template = json_template(&recorded, "body", points);
+      return points.unquote_typed(&serde_json::to_string(&template).unwrap_or_default());
+    }
+  }
+
+  if mime_type.contains("x-www-form-urlencoded") {
+    let params: Vec<(String, String)> = if post_data.params.is_empty() {
+      url::form_urlencoded::parse(post_data.text.as_bytes()).into_owned().collect()
+    } else {
+      post_data.params.iter().map(|param| (param.name.clone(), param.value.clone())).collect()
+    };
+
+    return params.iter()
+      .map(|(name, value)| format!("{}={}", name, points.add(name, value)))
+      .collect::<Vec<_>>()
+      .join("&");
+  }
+
+  if post_data.text.is_empty() {
+    String::new()
+  } else {
+    points.add("body", &post_data.text)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use crate::fuzzer::body::{render_body, BodyFormat};
+  use super::*;
+
+  const HAR: &str = r#"{
+    "log": {
+      "entries": [
+        {
+          "request": {
+            "method": "POST",
+            "url": "https://api.test.com/users?invite=abc",
+            "headers": [
+              { "name": ":authority", "value": "api.test.com" },
+              { "name": "Content-Type", "value": "application/json" },
+              { "name": "Content-Length", "value": "30" },
+              { "name": "Authorization", "value": "Bearer token" },
+              { "name": "Cookie", "value": "session=1" }
+            ],
+            "cookies": [{ "name": "session", "value": "1" }],
+            "postData": { "mimeType": "application/json", "text": "{\"name\":\"bob\",\"roles\":[\"admin\"],\"age\":42,\"active\":true}" }
+          },
+          "response": { "status": 201, "content": { "mimeType": "application/json" } }
+        },
+        {
+          "request": { "method": "POST", "url": "https://api.test.com/users?invite=def", "headers": [] },
+          "response": { "status": 201, "content": { "mimeType": "application/json" } }
+        },
+        {
+          "request": {
+            "method": "POST",
+            "url": "https://www.test.com/login",
+            "headers": [{ "name": "Content-Type", "value": "application/x-www-form-urlencoded" }],
+            "postData": { "mimeType": "application/x-www-form-urlencoded", "text": "user=bob&pass=secret" }
+          },
+          "response": { "status": 302, "content": { "mimeType": "text/html" } }
+        },
+        {
+          "request": { "method": "GET", "url": "https://cdn.other.com/app.js" },
+          "response": { "status": 200, "content": { "mimeType": "application/javascript" } }
+        }
+      ]
+    }
+  }"#;
+
+  fn point_names(path: &Path) -> Vec<String> {
+    path.fuzz_points().iter().map(|point| point.name().clone()).collect()
+  }
+
+  #[test]
+  fn test_import_entry() {
+    let filter = HarFilter { hosts: vec!["api.test.com".into()], content_types: vec![] };
+    let input = import("capture.har", HAR, &filter).unwrap();
+
+    assert_eq!(input.host(), "https://api.test.com");
+    assert_eq!(input.paths().len(), 1);
+
+    let path = &input.paths()[0];
+    assert_eq!(path.method(), &HttpMethod::POST);
+    assert_eq!(path.endpoint(), "/users");
+    assert_eq!(path.expected_status(), &201);
+    assert_eq!(path.query_parameters()[0].template(), "{fuzz_invite}");
+    assert_eq!(path.content_type(), Some("application/json"));
+
+    let headers: Vec<(&String, &str)> = path.headers().iter().map(|header| (header.name(), header.template())).collect();
+    assert_eq!(headers, vec![
+      (&"Content-Type".to_string(), "application/json"),
+      (&"Authorization".to_string(), "{fuzz_Authorization}"),
+      (&"Cookie".to_string(), "session={fuzz_cookie_session}"),
+    ]);
+    assert_eq!(path.body(), r#"{"active":{fuzz_active},"age":{fuzz_age},"name":"{fuzz_name}","roles":["{fuzz_roles}"]}"#);
+    assert_eq!(point_names(path), vec!["fuzz_invite", "fuzz_Authorization", "fuzz_cookie_session", "fuzz_active", "fuzz_age", "fuzz_name", "fuzz_roles"]);
+
+    // the defaults render the recorded body again, with its types
+    let defaults: HashMap<String, String> = path.fuzz_points().iter().map(|point| (point.name().clone(), point.default().clone())).collect();
+    let replayed: serde_json::Value = serde_json::from_str(&render_body(path.body(), BodyFormat::Json, &defaults)).unwrap();
+    assert_eq!(replayed, serde_json::json!({ "name": "bob", "roles": ["admin"], "age": 42, "active": true }));
+    assert_eq!(path.fuzz_point("fuzz_Authorization").unwrap().default(), "Bearer token");
+  }
+
+  #[test]
+  fn test_import_form_entry_by_content_type() {
+    let filter = HarFilter { hosts: vec![], content_types: vec!["HTML".into()] };
+    let input = import("capture.har", HAR, &filter).unwrap();
+
+    assert_eq!(input.host(), "https://www.test.com");
+    let path = &input.paths()[0];
+    assert_eq!(path.expected_status(), &302);
+    assert_eq!(path.body(), "user={fuzz_user}&pass={fuzz_pass}");
+    assert_eq!(path.fuzz_point("fuzz_pass").unwrap().default(), "secret");
+  }
+
+  #[test]
+  fn test_host_filter_matches_subdomains() {
+    let filter = HarFilter { hosts: vec!["test.com".into()], content_types: vec![] };
+    let input = import("capture.har", HAR, &filter).unwrap();
+
+    // the www entry is on another host than the first one kept
+    assert_eq!(input.paths().len(), 1);
+    assert_eq!(input.paths()[0].endpoint(), "/users");
+  }
+
+  #[test]
+  fn test_import_without_matching_entries() {
+    let filter = HarFilter { hosts: vec!["missing.com".into()], content_types: vec![] };
+
+    assert!(matches!(import("capture.har", HAR, &filter), Err(FuzzyError::Import { .. })));
+    assert!(matches!(import("capture.har", "{}", &HarFilter::default()), Err(FuzzyError::Import { .. })));
+  }
+}
@@ -0,0 +1,160 @@
+pub mod curl;
+pub mod har;
+pub mod openapi;
+pub mod raw;
+pub mod request;
+
+use url::Url;
+use crate::domain::input::{FuzzPoint, HttpMethod};
+
+/// The fuzz points of one imported request, each with a placeholder name unique within the request.
+#[derive(Debug, Default)]
+pub struct FuzzPoints {
+  points: Vec<FuzzPoint>,
+  /// Placeholders of json numbers, booleans and nulls, written unquoted so their values keep the type.
+  typed: Vec<String>,
+}
+
+impl FuzzPoints {
+  /// Adds a fuzz point named after `name`, returning its placeholder, e.g. `{fuzz_user_id}` for `user-id`.
+  pub fn add(&mut self, name: &str, default: &str) -> String {
+    let base: String = name.chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+      .collect();
+    let base = format!("fuzz_{}", base.trim_matches('_'));
+    let base = base.trim_end_matches('_').to_string();
+
+    let mut point_name = base.clone();
+    let mut suffix = 2;
+    while self.points.iter().any(|point| point.name() == &point_name) {
+      point_name = format!("{}_{}", base, suffix);
+      suffix += 1;
+    }
+
+    self.points.push(FuzzPoint::new(point_name.clone(), Vec::new(), default.to_string()));
+    format!("{{{}}}", point_name)
+  }
+
+  /// Adds a fuzz point for a json leaf that is not a string, see `unquote_typed`.
+  pub fn add_typed(&mut self, name: &str, default: &str) -> String {
+    let placeholder = self.add(name, default);
+    self.typed.push(placeholder.clone());
+    placeholder
+  }
+
+  /// Unquotes the placeholders of typed json leaves in a serialized template, e.g. `"age": "{fuzz_age}"` becomes
+  /// `"age": {fuzz_age}`, which the json body renderer fills with the value as a json literal.
+  pub fn unquote_typed(&self, json: &str) -> String {
+    self.typed.iter().fold(json.to_string(), |json, placeholder| {
+      json.replace(&format!("\"{}\"", placeholder), placeholder)
+    })
+  }
+
+  pub fn into_points(self) -> Vec<FuzzPoint> {
+    self.points
+  }
+}
+
+/// Splits a server url into the `host` and `base_path` of an input, e.g. `https://api.test.com/v1/` into
+/// `https://api.test.com` and `/v1`.
+pub fn split_server_url(url: &Url) -> (String, String) {
+  let host = url.origin().ascii_serialization();
+  let base_path = url.path().trim_end_matches('/').to_string();
+
+  (host, base_path)
+}
+
+/// The method of a recorded request, `None` for methods that cannot be fuzzed such as `TRACE`.
+pub fn parse_method(method: &str) -> Option<HttpMethod> {
+  match method.to_ascii_uppercase().as_str() {
+    "GET" => Some(HttpMethod::GET),
+    "POST" => Some(HttpMethod::POST),
+    "PUT" => Some(HttpMethod::PUT),
+    "PATCH" => Some(HttpMethod::PATCH),
+    "DELETE" => Some(HttpMethod::DELETE),
+    "HEAD" => Some(HttpMethod::HEAD),
+    "OPTIONS" => Some(HttpMethod::OPTIONS),
+    _ => None,
+  }
+}
+
+/// A recorded json document with every scalar replaced by a placeholder whose default is the recorded value.
+/// Numbers, booleans and nulls get typed placeholders, so serialize it through `FuzzPoints::unquote_typed`.
+pub fn json_template(value: &serde_json::Value, name: &str, points: &mut FuzzPoints) -> serde_json::Value {
+  match value {
+    serde_json::Value::Object(object) => serde_json::Value::Object(
+      object.iter()
+        .map(|(key, value)| (key.clone(), json_template(value, key, points)))
+        .collect()
+    ),
+    serde_json::Value::Array(items) => serde_json::Value::Array(
+      items.iter().map(|item| json_template(item, name, points)).collect()
+    ),
+    serde_json::Value::String(recorded) => serde_json::Value::String(points.add(name, recorded)),
+    scalar => serde_json::Value::String(points.add_typed(name, &scalar.to_string())),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_fuzz_point_names_are_unique() {
+    let mut points = FuzzPoints::default();
+
+    assert_eq!(points.add("user-id", "1"), "{fuzz_user_id}");
+    assert_eq!(points.add("user_id", "2"), "{fuzz_user_id_2}");
+    assert_eq!(points.add("", ""), "{fuzz}");
+    assert_eq!(points.add("$", ""), "{fuzz_2}");
+
+    let points = points.into_points();
+    assert_eq!(points.len(), 4);
+    assert_eq!(points[1].default(), "2");
+  }
+
+  #[test]
+  fn test_unquote_typed() {
+    let mut points = FuzzPoints::default();
+    let name = points.add("name", "bob");
+    let age = points.add_typed("age", "42");
+
+    let json = serde_json::to_string(&serde_json::json!({ "name": name, "age": age })).unwrap();
+    assert_eq!(points.unquote_typed(&json), r#"{"age":{fuzz_age},"name":"{fuzz_name}"}"#);
+  }
+
+  #[test]
+  fn test_json_template() {
+    let mut points = FuzzPoints::default();
+    let recorded = serde_json::json!({ "user": { "name": "bob", "age": 42 }, "tags": ["a", "b"], "active": null });
+
+    let template = json_template(&recorded, "body", &mut points);
+
+    assert_eq!(template, serde_json::json!({
+      "user": { "name": "{fuzz_name}", "age": "{fuzz_age}" },
+      "tags": ["{fuzz_tags}", "{fuzz_tags_2}"],
+      "active": "{fuzz_active}",
+    }));
+    assert_eq!(
+      points.unquote_typed(&serde_json::to_string(&template).unwrap()),
+      r#"{"active":{fuzz_active},"tags":["{fuzz_tags}","{fuzz_tags_2}"],"user":{"age":{fuzz_age},"name":"{fuzz_name}"}}"#
+    );
+    let defaults: Vec<String> = points.into_points().iter().map(|point| point.default().clone()).collect();
+    assert_eq!(defaults, vec!["null", "a", "b", "42", "bob"]);
+  }
+
+  #[test]
+  fn test_parse_method() {
+    assert_eq!(parse_method("post"), Some(HttpMethod::POST));
+    assert_eq!(parse_method("TRACE"), None);
+  }
+
+  #[test]
+  fn test_split_server_url() {
+    let url = Url::parse("https://api.test.com:8443/v1/").unwrap();
+    assert_eq!(split_server_url(&url), ("https://api.test.com:8443".to_string(), "/v1".to_string()));
+
+    let url = Url::parse("http://localhost").unwrap();
+    assert_eq!(split_server_url(&url), ("http://localhost".to_string(), "".to_string()));
+  }
+}
@@ -0,0 +1,533 @@
+use serde_yaml::{Mapping, Value};
+use url::Url;
+use crate::domain::input::{AttackStrategy, FuzzyInput, HeaderParameter, HttpMethod, Path, QueryParameter};
+use crate::error::FuzzyError;
+use crate::import::{split_server_url, FuzzPoints};
+
+/// Host used when the document only has a relative server url or none at all.
+pub const DEFAULT_HOST: &str = "http://localhost";
+
+/// Schemas nested deeper than this are fuzzed as a whole, as are references to a schema that is being expanded.
+const MAX_SCHEMA_DEPTH: usize = 8;
+
+/// Methods in the order they are imported, `trace` has no `HttpMethod`.
+const METHODS: [(&str, HttpMethod); 7] = [
+  ("get", HttpMethod::GET),
+  ("post", HttpMethod::POST),
+  ("put", HttpMethod::PUT),
+  ("patch", HttpMethod::PATCH),
+  ("delete", HttpMethod::DELETE),
+  ("head", HttpMethod::HEAD),
+  ("options", HttpMethod::OPTIONS),
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Version {
+  Swagger2,
+  OpenApi3,
+}
+
+/// Generates an input with one path per operation of an OpenAPI 3 or Swagger 2 document, in json or yaml.
+///
+/// Every path, query and header parameter and every leaf of the request body becomes a fuzz point, using the
+/// documented example or default as the value sent while other points are fuzzed.
+pub fn import(file: &str, content: &str) -> Result<FuzzyInput, FuzzyError> {
+  let root: Value = serde_yaml::from_str(content).map_err(|err| FuzzyError::yaml(file, err))?;
+  let document = Document::new(file, &root)?;
+  let (host, base_path) = document.server();
+
+  let mut paths = Vec::new();
+  if let Some(items) = root["paths"].as_mapping() {
+    for (endpoint, item) in items {
+      let endpoint = key_to_string(endpoint);
+      let item = document.resolve(item);
+
+      for (key, method) in METHODS {
+        if let Some(operation) = item.get(key) {
+          paths.push(document.operation(&endpoint, method, item, document.resolve(operation)));
+        }
+      }
+    }
+  }
+
+  if paths.is_empty() {
+    return Err(FuzzyError::Import { path: file.to_string(), message: "no operations found".into() });
+  }
+
+  Ok(FuzzyInput::new(host, base_path, paths))
+}
+
+struct Document<'a> {
+  root: &'a Value,
+  version: Version,
+}
+
+impl<'a> Document<'a> {
+  fn new(file: &str, root: &'a Value) -> Result<Self, FuzzyError> {
+    let version = if root.get("openapi").is_some() {
+      Version::OpenApi3
+    } else if root.get("swagger").is_some() {
+      Version::Swagger2
+    } else {
+      return Err(FuzzyError::Import { path: file.to_string(), message: "not an OpenAPI 3 or Swagger 2 document".into() });
+    };
+
+    Ok(Self { root, version })
+  }
+
+  /// Follows local `$ref`s, e.g. `#/components/schemas/User`, returning null for refs that cannot be resolved.
+  fn resolve(&self, value: &'a Value) -> &'a Value {
+    let mut value = value;
+
+    for _ in 0..MAX_SCHEMA_DEPTH {
+      let reference = match value.get("$ref").and_then(Value::as_str) {
+        Some(reference) => reference,
+        None => return value,
+      };
+
+      value = reference.strip_prefix("#/")
+        .map(|pointer| {
+          pointer.split('/')
+            .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+            .fold(self.root, |node, segment| node.get(segment.as_str()).unwrap_or(&Value::Null))
+        })
+        .unwrap_or_else(|| {
+          log::warn!("only local references are supported, skipping {}", reference);
+          &Value::Null
+        });
+    }
+
+    value
+  }
+
+  /// The host and base path of the first server, with server variables set to their defaults.
+  fn server(&self) -> (String, String) {
+    let url = match self.version {
+      Version::OpenApi3 => {
+        let server = &self.root["servers"][0];
+        let mut url = server["url"].as_str().unwrap_or_default().to_string();
+
+        if let Some(variables) = server["variables"].as_mapping() {
+          for (name, variable) in variables {
+            let default = scalar_to_string(&variable["default"]).unwrap_or_default();
+            url = url.replace(&format!("{{{}}}", key_to_string(name)), &default);
+          }
+        }
+        url
+      }
+      Version::Swagger2 => {
+        let scheme = self.root["schemes"][0].as_str().unwrap_or("https");
+        let base_path = self.root["basePath"].as_str().unwrap_or_default();
+        match self.root["host"].as_str() {
+          Some(host) => format!("{}://{}{}", scheme, host, base_path),
+          None => base_path.to_string(),
+        }
+      }
+    };
+
+    match Url::parse(&url) {
+      Ok(url) => split_server_url(&url),
+      Err(_) => {
+        log::warn!("no absolute server url, using {} as the host", DEFAULT_HOST);
+        (DEFAULT_HOST.to_string(), url.trim_end_matches('/').to_string())
+      }
+    }
+  }
+
+  fn operation(&self, endpoint: &str, method: HttpMethod, item: &'a Value, operation: &'a Value) -> Path {
+    let mut points = FuzzPoints::default();
+    let mut endpoint = endpoint.to_string();
+    let mut query_parameters = Vec::new();
+    let mut headers = Vec::new();
+    let mut body = String::new();
+
+    for parameter in self.parameters(item, operation) {
+      let name = parameter["name"].as_str().unwrap_or_default();
+      let schema = self.parameter_schema(parameter);
+
+      match parameter["in"].as_str().unwrap_or_default() {
+        "path" => {
+          let placeholder = points.add(name, &self.sample(parameter, schema));
+          endpoint = endpoint.replace(&format!("{{{}}}", name), &placeholder);
+        }
+        "query" => {
+          let placeholder = points.add(name, &self.sample(parameter, schema));
+          query_parameters.push(QueryParameter::new(name.to_string(), true, Some(placeholder)));
+        }
+        "header" => {
+          let placeholder = points.add(name, &self.sample(parameter, schema));
+          headers.push(HeaderParameter::new(name.to_string(), Some(placeholder), true));
+        }
+        "body" => {
+          headers.push(content_type_header("application/json"));
+          body = self.json_body(schema, &mut points);
+        }
+        "formData" => {
+          if body.is_empty() {
+            headers.push(content_type_header("application/x-www-form-urlencoded"));
+          } else {
+            body.push('&');
+          }
+          let placeholder = points.add(name, &self.sample(parameter, schema));
+          body.push_str(&format!("{}={}", name, placeholder));
+        }
+        location => log::debug!("not fuzzing {} parameter {} of {}", location, name, endpoint),
+      }
+    }
+
+    let content = self.resolve(&operation["requestBody"])["content"].as_mapping();
+    let media = content.and_then(|content| {
+      content.iter()
+        .find(|(media_type, _)| key_to_string(media_type).contains("json"))
+        .or_else(|| content.iter().next())
+    });
+
+    if let Some((media_type, media)) = media {
+      let media_type = key_to_string(media_type);
+      let schema = self.resolve(&media["schema"]);
+
+      headers.push(content_type_header(&media_type));
+      body = if media_type.contains("json") {
+        self.json_body(schema, &mut points)
+      } else if media_type.contains("x-www-form-urlencoded") {
+        self.form_body(schema, &mut points)
+      } else {
+        points.add("body", &self.sample(media, schema))
+      };
+    }
+
+    Path::new(endpoint, method, expected_status(&operation["responses"]), vec![], "".into(), query_parameters, headers, body)
+      .with_attack(AttackStrategy::Sniper, points.into_points())
+  }
+
+  /// Path item parameters overridden by operation parameters with the same name and location.
+  fn parameters(&self, item: &'a Value, operation: &'a Value) -> Vec<&'a Value> {
+    let mut parameters: Vec<&Value> = Vec::new();
+    let declared = [&item["parameters"], &operation["parameters"]];
+
+    for parameter in declared.iter().filter_map(|list| list.as_sequence()).flatten() {
+      let parameter = self.resolve(parameter);
+      parameters.retain(|existing| existing["name"] != parameter["name"] || existing["in"] != parameter["in"]);
+      parameters.push(parameter);
+    }
+
+    parameters
+  }
+
+  /// Swagger 2 describes non body parameters inline instead of with a schema.
+  fn parameter_schema(&self, parameter: &'a Value) -> &'a Value {
+    match parameter.get("schema") {
+      Some(schema) => self.resolve(schema),
+      None => parameter,
+    }
+  }
+
+  fn json_body(&self, schema: &'a Value, points: &mut FuzzPoints) -> String {
+    let template = self.json_template(schema, "body", points, &mut Vec::new());
+    points.unquote_typed(&serde_json::to_string_pretty(&template).unwrap_or_default())
+  }
+
+  /// A json document shaped like the schema, with a placeholder in every leaf. Integer, number and boolean leaves are
+  /// typed placeholders, so they are sent unquoted.
+  fn json_template(&self, schema: &'a Value, name: &str, points: &mut FuzzPoints, expanding: &mut Vec<&'a Value>) -> serde_json::Value {
+    let schema = self.resolve(schema);
+
+    if expanding.len() < MAX_SCHEMA_DEPTH && !expanding.iter().any(|parent| std::ptr::eq(*parent, schema)) {
+      expanding.push(schema);
+      let template = self.expand_schema(schema, name, points, expanding);
+      expanding.pop();
+
+      if let Some(template) = template {
+        return template;
+      }
+    }
+
+    let sample = self.sample(schema, schema);
+    match schema["type"].as_str() {
+      Some("integer") | Some("number") | Some("boolean") => serde_json::Value::String(points.add_typed(name, &sample)),
+      _ => serde_json::Value::String(points.add(name, &sample)),
+    }
+  }
+
+  /// The template of an object, array or composed schema, `None` for leaves.
+  fn expand_schema(&self, schema: &'a Value, name: &str, points: &mut FuzzPoints, expanding: &mut Vec<&'a Value>) -> Option<serde_json::Value> {
+    if let Some(variants) = schema["allOf"].as_sequence() {
+      let mut merged = serde_json::Map::new();
+      for variant in variants {
+        if let serde_json::Value::Object(object) = self.json_template(variant, name, points, expanding) {
+          merged.extend(object);
+        }
+      }
+      return Some(serde_json::Value::Object(merged));
+    }
+
+    if let Some(variant) = schema["oneOf"].get(0).or_else(|| schema["anyOf"].get(0)) {
+      return Some(self.json_template(variant, name, points, expanding));
+    }
+
+    if let Some(properties) = schema["properties"].as_mapping() {
+      let object = properties.iter()
+        .map(|(property, property_schema)| {
+          let property = key_to_string(property);
+          let value = self.json_template(property_schema, &property, points, expanding);
+          (property, value)
+        })
+        .collect();
+      return Some(serde_json::Value::Object(object));
+    }
+
+    if schema["type"].as_str() == Some("array") {
+      return Some(serde_json::Value::Array(vec![self.json_template(&schema["items"], name, points, expanding)]));
+    }
+
+    None
+  }
+
+  fn form_body(&self, schema: &'a Value, points: &mut FuzzPoints) -> String {
+    match schema["properties"].as_mapping() {
+      Some(properties) => properties.iter()
+        .map(|(property, property_schema)| {
+          let property = key_to_string(property);
+          let property_schema = self.resolve(property_schema);
+          format!("{}={}", property, points.add(&property, &self.sample(property_schema, property_schema)))
+        })
+        .collect::<Vec<_>>()
+        .join("&"),
+      None => points.add("body", &self.sample(schema, schema)),
+    }
+  }
+
+  /// A plausible value for a parameter or schema, sent while other points are fuzzed.
+  fn sample(&self, described: &Value, schema: &Value) -> String {
+    let documented = [&described["example"], &schema["example"], &schema["default"], &schema["enum"][0]];
+    if let Some(value) = documented.iter().find_map(|value| scalar_to_string(value)) {
+      return value;
+    }
+
+    match schema["type"].as_str() {
+      Some("integer") | Some("number") => "1".into(),
+      Some("boolean") => "true".into(),
+      _ => match schema["format"].as_str() {
+        Some("uuid") => "00000000-0000-0000-0000-000000000000".into(),
+        Some("date") => "2020-01-01".into(),
+        Some("date-time") => "2020-01-01T00:00:00Z".into(),
+        Some("email") => "test@example.com".into(),
+        _ => "test".into(),
+      },
+    }
+  }
+}
+
+/// The lowest documented 2xx status, 200 when none is documented.
+fn expected_status(responses: &Value) -> u16 {
+  responses.as_mapping()
+    .map(Mapping::keys)
+    .into_iter()
+    .flatten()
+    .filter_map(|status| match key_to_string(status).to_ascii_uppercase().as_str() {
+      "2XX" => Some(200),
+      status => status.parse::<u16>().ok(),
+    })
+    .filter(|status| (200..300).contains(status))
+    .min()
+    .unwrap_or(200)
+}
+
+fn content_type_header(media_type: &str) -> HeaderParameter {
+  HeaderParameter::new("Content-Type".into(), Some(media_type.to_string()), false)
+}
+
+/// Yaml keys can be numbers, e.g. response codes.
+fn key_to_string(key: &Value) -> String {
+  scalar_to_string(key).unwrap_or_default()
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+  match value {
+    Value::String(value) => Some(value.clone()),
+    Value::Number(value) => Some(value.to_string()),
+    Value::Bool(value) => Some(value.to_string()),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+  use crate::fuzzer::body::{render_body, BodyFormat};
+  use super::*;
+
+  const OPENAPI: &str = r##"
+openapi: 3.0.1
+servers:
+  - url: https://{env}.test.com/api/v1
+    variables:
+      env:
+        default: staging
+paths:
+  /users/{userId}:
+    parameters:
+      - $ref: "#/components/parameters/UserId"
+    get:
+      parameters:
+        - name: fields
+          in: query
+          schema: { type: string, example: name }
+        - name: X-Tenant
+          in: header
+          schema: { type: string }
+        - name: session
+          in: cookie
+          schema: { type: string }
+      responses:
+        "404": { description: missing }
+        "200": { description: found }
+    put:
+      requestBody:
+        content:
+          application/json:
+            schema:
+              $ref: "#/components/schemas/User"
+      responses:
+        204: { description: updated }
+  /login:
+    post:
+      requestBody:
+        content:
+          application/x-www-form-urlencoded:
+            schema:
+              properties:
+                user: { type: string }
+                remember: { type: boolean }
+      responses:
+        default: { description: anything }
+components:
+  parameters:
+    UserId:
+      name: userId
+      in: path
+      required: true
+      schema: { type: integer, example: 42 }
+  schemas:
+    User:
+      allOf:
+        - properties:
+            name: { type: string, default: bob }
+            age: { type: integer, example: 30 }
+        - properties:
+            tags:
+              type: array
+              items: { type: string }
+            manager:
+              $ref: "#/components/schemas/User"
+"##;
+
+  const SWAGGER: &str = r#"{
+    "swagger": "2.0",
+    "host": "legacy.test.com",
+    "basePath": "/v2",
+    "schemes": ["http"],
+    "paths": {
+      "/pets": {
+        "post": {
+          "parameters": [
+            { "name": "pet", "in": "body", "schema": { "properties": { "name": { "type": "string" } } } },
+            { "name": "limit", "in": "query", "type": "integer" }
+          ],
+          "responses": { "201": { "description": "created" } }
+        }
+      }
+    }
+  }"#;
+
+  fn point_defaults(path: &Path) -> Vec<(String, String)> {
+    path.fuzz_points().iter().map(|point| (point.name().clone(), point.default().clone())).collect()
+  }
+
+  #[test]
+  fn test_import_openapi_parameters() {
+    let input = import("spec.yml", OPENAPI).unwrap();
+
+    assert_eq!(input.host(), "https://staging.test.com");
+    assert_eq!(input.base_path(), "/api/v1");
+    assert_eq!(input.paths().len(), 3);
+
+    let get = &input.paths()[0];
+    assert_eq!(get.method(), &HttpMethod::GET);
+    assert_eq!(get.endpoint(), "/users/{fuzz_userId}");
+    assert_eq!(get.expected_status(), &200);
+    assert_eq!(get.query_parameters()[0].name(), "fields");
+    assert_eq!(get.query_parameters()[0].template(), "{fuzz_fields}");
+    assert_eq!(get.headers().len(), 1);
+    assert_eq!(get.headers()[0].template(), "{fuzz_X_Tenant}");
+    assert_eq!(point_defaults(get), vec![
+      ("fuzz_userId".to_string(), "42".to_string()),
+      ("fuzz_fields".to_string(), "name".to_string()),
+      ("fuzz_X_Tenant".to_string(), "test".to_string()),
+    ]);
+  }
+
+  #[test]
+  fn test_import_openapi_json_body() {
+    let input = import("spec.yml", OPENAPI).unwrap();
+
+    let put = &input.paths()[1];
+    assert_eq!(put.method(), &HttpMethod::PUT);
+    assert_eq!(put.expected_status(), &204);
+    assert_eq!(put.content_type(), Some("application/json"));
+
+    assert!(put.body().contains(r#""age": {fuzz_age}"#));
+    let values = HashMap::from([("fuzz_age".to_string(), "30".to_string())]);
+    let body: serde_json::Value = serde_json::from_str(&render_body(put.body(), BodyFormat::Json, &values)).unwrap();
+    assert_eq!(body["name"], "{fuzz_name}");
+    assert_eq!(body["age"], 30);
+    assert_eq!(body["tags"], serde_json::json!(["{fuzz_tags}"]));
+    assert_eq!(body["manager"], "{fuzz_manager}");
+    assert!(point_defaults(put).contains(&("fuzz_name".to_string(), "bob".to_string())));
+    assert!(point_defaults(put).contains(&("fuzz_age".to_string(), "30".to_string())));
+  }
+
+  #[test]
+  fn test_import_openapi_form_body() {
+    let input = import("spec.yml", OPENAPI).unwrap();
+
+    let post = &input.paths()[2];
+    assert_eq!(post.endpoint(), "/login");
+    assert_eq!(post.expected_status(), &200);
+    assert_eq!(post.content_type(), Some("application/x-www-form-urlencoded"));
+    assert_eq!(post.body(), "user={fuzz_user}&remember={fuzz_remember}");
+    assert_eq!(point_defaults(post)[1], ("fuzz_remember".to_string(), "true".to_string()));
+  }
+
+  #[test]
+  fn test_import_swagger() {
+    let input = import("spec.json", SWAGGER).unwrap();
+
+    assert_eq!(input.host(), "http://legacy.test.com");
+    assert_eq!(input.base_path(), "/v2");
+
+    let post = &input.paths()[0];
+    assert_eq!(post.expected_status(), &201);
+    assert_eq!(post.query_parameters()[0].template(), "{fuzz_limit}");
+    assert_eq!(serde_json::from_str::<serde_json::Value>(post.body()).unwrap()["name"], "{fuzz_name}");
+    assert_eq!(point_defaults(post)[1], ("fuzz_limit".to_string(), "1".to_string()));
+  }
+
+  #[test]
+  fn test_imported_input_round_trips() {
+    let input = import("spec.yml", OPENAPI).unwrap();
+    let yaml = serde_yaml::to_string(&input).unwrap();
+
+    let reloaded: FuzzyInput = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(reloaded.paths().len(), 3);
+    assert_eq!(reloaded.paths()[1].body(), input.paths()[1].body());
+    assert!(!yaml.contains("request_id_header"));
+    assert!(!yaml.contains("settings"));
+  }
+
+  #[test]
+  fn test_import_rejects_other_documents() {
+    assert!(matches!(import("spec.yml", "title: nothing"), Err(FuzzyError::Import { .. })));
+    assert!(matches!(import("spec.yml", "openapi: 3.0.0\npaths: {}"), Err(FuzzyError::Import { .. })));
+  }
+}
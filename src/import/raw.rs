@@ -0,0 +1,106 @@
+use crate::domain::input::FuzzyInput;
+use crate::error::FuzzyError;
+use crate::import::parse_method;
+use crate::import::request::MarkedRequest;
+
+/// Headers computed from the request itself, never imported.
+const SKIPPED_HEADERS: [&str; 2] = ["host", "content-length"];
+
+/// Generates an input from a raw http request, e.g. copied from a proxy, with fuzz positions marked as `§value§`.
+///
+/// The request target can be a path, sent to the `Host` header over `scheme`, or an absolute url.
+pub fn import(file: &str, content: &str, scheme: &str) -> Result<FuzzyInput, FuzzyError> {
+  parse(file, content, scheme)?.to_input(file)
+}
+
+fn parse(file: &str, content: &str, scheme: &str) -> Result<MarkedRequest, FuzzyError> {
+  let invalid = |message: String| FuzzyError::Import { path: file.to_string(), message };
+
+  let content = content.trim_start().replace("\r\n", "\n");
+  let (head, body) = content.split_once("\n\n").unwrap_or((&content, ""));
+  let mut lines = head.lines();
+
+  let request_line = lines.next().unwrap_or_default();
+  let mut parts = request_line.split_whitespace();
+  let (method, target) = match (parts.next(), parts.next()) {
+    (Some(method), Some(target)) => (method, target),
+    _ => return Err(invalid(format!("invalid request line: {:?}", request_line))),
+  };
+  let method = parse_method(method).ok_or_else(|| invalid(format!("unsupported method {}", method)))?;
+
+  let mut host: Option<String> = None;
+  let mut headers = Vec::new();
+  for line in lines {
+    let (name, value) = line.split_once(':').ok_or_else(|| invalid(format!("invalid header: {:?}", line)))?;
+    let (name, value) = (name.trim(), value.trim());
+
+    if name.eq_ignore_ascii_case("host") {
+      host = Some(value.to_string());
+    }
+    if !SKIPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+      headers.push((name.to_string(), value.to_string()));
+    }
+  }
+
+  let url = if target.starts_with('/') {
+    let host = host.ok_or_else(|| invalid("no Host header for the request path".into()))?;
+    format!("{}://{}{}", scheme, host, target)
+  } else {
+    target.to_string()
+  };
+
+  Ok(MarkedRequest::new(method, url, headers, body.trim_end_matches('\n').to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::domain::input::HttpMethod;
+
+  const REQUEST: &str = "POST /login?next=§/home§ HTTP/1.1\r\n\
+    Host: app.test.com\r\n\
+    Content-Type: application/x-www-form-urlencoded\r\n\
+    Content-Length: 27\r\n\
+    Cookie: session=§abc§\r\n\
+    \r\n\
+    user=§bob§&pass=secret\r\n";
+
+  #[test]
+  fn test_parse() {
+    let request = parse("request.txt", REQUEST, "https").unwrap();
+
+    assert_eq!(request.method(), &HttpMethod::POST);
+    assert_eq!(request.url(), "https://app.test.com/login?next=§/home§");
+    assert_eq!(request.headers(), &vec![
+      ("Content-Type".to_string(), "application/x-www-form-urlencoded".to_string()),
+      ("Cookie".to_string(), "session=§abc§".to_string()),
+    ]);
+    assert_eq!(request.body(), "user=§bob§&pass=secret");
+  }
+
+  #[test]
+  fn test_parse_absolute_target() {
+    let request = parse("request.txt", "GET http://proxy.test.com/a HTTP/1.1\nHost: other.com\n", "https").unwrap();
+
+    assert_eq!(request.url(), "http://proxy.test.com/a");
+    assert_eq!(request.body(), "");
+  }
+
+  #[test]
+  fn test_import() {
+    let input = import("request.txt", REQUEST, "http").unwrap();
+    assert_eq!(input.host(), "http://app.test.com");
+
+    let path = &input.paths()[0];
+    assert_eq!(path.endpoint(), "/login");
+    assert_eq!(path.query_parameters()[0].template(), "{fuzz_next}");
+    assert_eq!(path.body(), "user={fuzz_user}&pass=secret");
+  }
+
+  #[test]
+  fn test_parse_errors() {
+    assert!(matches!(parse("request.txt", "GET /a HTTP/1.1\n", "https"), Err(FuzzyError::Import { .. })));
+    assert!(matches!(parse("request.txt", "nonsense", "https"), Err(FuzzyError::Import { .. })));
+    assert!(matches!(parse("request.txt", "GET /a HTTP/1.1\nHost: a.com\nbroken\n", "https"), Err(FuzzyError::Import { .. })));
+  }
+}
@@ -0,0 +1,173 @@
+use url::{form_urlencoded, Url};
+use crate::domain::input::{AttackStrategy, FuzzyInput, HeaderParameter, HttpMethod, Path, QueryParameter};
+use crate::error::FuzzyError;
+use crate::import::{split_server_url, FuzzPoints};
+
+/// Wraps the values to fuzz in a curl command or raw request, e.g. `id=§42§`.
+pub const MARKER: char = '§';
+
+/// A request copied from a curl command or a proxy, with fuzz positions marked as `§value§`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkedRequest {
+  method: HttpMethod,
+  url: String,
+  headers: Vec<(String, String)>,
+  body: String,
+}
+
+impl MarkedRequest {
+  pub fn new(method: HttpMethod, url: String, headers: Vec<(String, String)>, body: String) -> Self {
+    Self { method, url, headers, body }
+  }
+
+  pub fn method(&self) -> &HttpMethod {
+    &self.method
+  }
+
+  pub fn url(&self) -> &String {
+    &self.url
+  }
+
+  pub fn headers(&self) -> &Vec<(String, String)> {
+    &self.headers
+  }
+
+  pub fn body(&self) -> &String {
+    &self.body
+  }
+
+  /// Replaces every marked value with a placeholder defaulting to it, named after the query parameter, header or
+  /// form field it is in.
+  pub fn to_input(&self, file: &str) -> Result<FuzzyInput, FuzzyError> {
+    let mut points = FuzzPoints::default();
+
+    let url_without_fragment = self.url.split('#').next().unwrap_or_default();
+    let (url, query) = url_without_fragment.split_once('?').unwrap_or((url_without_fragment, ""));
+    let url = Url::parse(&mark(url, "path", &mut points))
+      .map_err(|err| FuzzyError::Import { path: file.to_string(), message: format!("invalid url {}: {}", self.url, err) })?;
+    let (host, _) = split_server_url(&url);
+
+    let query_parameters = form_urlencoded::parse(query.as_bytes())
+      .map(|(name, value)| {
+        let marked = mark(&value, &name, &mut points);
+        QueryParameter::new(name.to_string(), marked != value, Some(marked))
+      })
+      .collect();
+
+    let headers: Vec<HeaderParameter> = self.headers.iter()
+      .map(|(name, value)| {
+        let marked = mark(value, name, &mut points);
+        HeaderParameter::new(name.clone(), Some(marked.clone()), &marked != value)
+      })
+      .collect();
+
+    let is_form = self.headers.iter()
+      .any(|(name, value)| name.eq_ignore_ascii_case("content-type") && value.contains("x-www-form-urlencoded"));
+    let body = if is_form {
+      self.body.split('&')
+        .map(|pair| match pair.split_once('=') {
+          Some((name, value)) => format!("{}={}", name, mark(value, name, &mut points)),
+          None => mark(pair, "body", &mut points),
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+    } else {
+      mark(&self.body, "body", &mut points)
+    };
+
+    let points = points.into_points();
+    if points.is_empty() {
+      log::warn!("no values are marked with {} in {}, nothing will be fuzzed", MARKER, file);
+    }
+
+    // the url parser encodes placeholders in the path, which the path template expects
+    let path = Path::new(url.path().to_string(), self.method.clone(), 200, vec![], "".into(), query_parameters, headers, body)
+      .with_attack(AttackStrategy::Sniper, points);
+
+    Ok(FuzzyInput::new(host, "".into(), vec![path]))
+  }
+}
+
+/// Replaces each `§value§` in the text with a placeholder named after `name`, an unpaired marker is kept as is.
+fn mark(text: &str, name: &str, points: &mut FuzzPoints) -> String {
+  let mut marked = String::new();
+  let mut rest = text;
+
+  while let Some(start) = rest.find(MARKER) {
+    let after_start = &rest[start + MARKER.len_utf8()..];
+    match after_start.find(MARKER) {
+      Some(end) => {
+        marked.push_str(&rest[..start]);
+        marked.push_str(&points.add(name, &after_start[..end]));
+        rest = &after_start[end + MARKER.len_utf8()..];
+      }
+      None => break,
+    }
+  }
+
+  marked.push_str(rest);
+  marked
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_mark() {
+    let mut points = FuzzPoints::default();
+
+    assert_eq!(mark("a §1§ b §2§", "id", &mut points), "a {fuzz_id} b {fuzz_id_2}");
+    assert_eq!(mark("no markers", "id", &mut points), "no markers");
+    assert_eq!(mark("unpaired § marker", "id", &mut points), "unpaired § marker");
+
+    let defaults: Vec<String> = points.into_points().iter().map(|point| point.default().clone()).collect();
+    assert_eq!(defaults, vec!["1", "2"]);
+  }
+
+  #[test]
+  fn test_to_input() {
+    let request = MarkedRequest::new(
+      HttpMethod::POST,
+      "https://api.test.com/users/§42§/orders?limit=§10§&sort=asc#top".into(),
+      vec![
+        ("Content-Type".into(), "application/x-www-form-urlencoded".into()),
+        ("Authorization".into(), "Bearer §token§".into()),
+        ("Accept".into(), "*/*".into()),
+      ],
+      "item=§book§&count=1".into(),
+    );
+
+    let input = request.to_input("request.txt").unwrap();
+    assert_eq!(input.host(), "https://api.test.com");
+
+    let path = &input.paths()[0];
+    assert_eq!(path.method(), &HttpMethod::POST);
+    assert_eq!(path.endpoint(), "/users/%7Bfuzz_path%7D/orders");
+    assert_eq!(path.query_parameters()[0].template(), "{fuzz_limit}");
+    assert!(!path.query_parameters()[1].fuzz());
+    assert_eq!(path.headers()[1].template(), "Bearer {fuzz_Authorization}");
+    assert!(!path.headers()[2].fuzz());
+    assert_eq!(path.body(), "item={fuzz_item}&count=1");
+
+    let defaults: Vec<(String, String)> = path.fuzz_points().iter()
+      .map(|point| (point.name().clone(), point.default().clone()))
+      .collect();
+    assert_eq!(defaults, vec![
+      ("fuzz_path".to_string(), "42".to_string()),
+      ("fuzz_limit".to_string(), "10".to_string()),
+      ("fuzz_Authorization".to_string(), "token".to_string()),
+      ("fuzz_item".to_string(), "book".to_string()),
+    ]);
+
+    let url = path.to_url(input.host(), input.base_path()).unwrap();
+    assert_eq!(url.as_str(), "https://api.test.com/users/%7Bfuzz_path%7D/orders?limit=%7Bfuzz_limit%7D&sort=asc");
+  }
+
+  #[test]
+  fn test_to_input_with_invalid_url() {
+    let request = MarkedRequest::new(HttpMethod::GET, "/relative".into(), vec![], "".into());
+
+    assert!(matches!(request.to_input("request.txt"), Err(FuzzyError::Import { .. })));
+  }
+}
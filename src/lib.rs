@@ -0,0 +1,5 @@
+pub mod cli;
+pub mod domain;
+pub mod error;
+pub mod fuzzer;
+pub mod import;